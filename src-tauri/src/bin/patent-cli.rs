@@ -1,13 +1,106 @@
 use anyhow::{Context, Result};
-use patentupload_lib::cli::{CliArgs, ImportMode};
+use patentupload_lib::cli::{
+    CliArgs, Command, ConsoleFormat, ImportMode, RunArgs, SuggestArgs, ValidateArgs, WatchArgs,
+};
 use patentupload_lib::excel;
-use patentupload_lib::commands::types::ColumnMapping;
+use patentupload_lib::commands::types::{
+    ColumnMapping, ConvertConfig, ConvertedFile, FileEntry, FileFormat, MappingSuggestion, MappingSuggestions,
+    MatchKind, OutputFormat, RunReport, RunSummary, SchemaCheck, UploadReport, UploadStatus, ValidationReport,
+    ValidationSchema,
+};
+use patentupload_lib::commands::upload::import_url;
 use reqwest;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
 use std::path::Path;
-use std::time::Duration;
+use std::process::ExitCode;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 use tokio::time::timeout;
 
+/// 上传请求出错后重试前的等待时间
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// 监控状态文件的默认文件名，位于输出目录下
+const WATCH_STATE_FILE: &str = ".patent-cli-watch.json";
+
+/// 监控中处理失败的文件第一次重试前的等待时间，之后每次失败翻倍
+const WATCH_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 监控中处理失败的文件重试前最长的等待时间
+const WATCH_MAX_RETRY_BACKOFF: Duration = Duration::from_secs(3600);
+
+/// 控制台输出格式，上传时按 --output-format 设置
+static CONSOLE_FORMAT: OnceLock<ConsoleFormat> = OnceLock::new();
+
+/// 输出进度信息：--output-format json 时输出到 stderr，stdout 只输出事件
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if json_events() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+fn json_events() -> bool {
+    CONSOLE_FORMAT.get() == Some(&ConsoleFormat::Json)
+}
+
+/// --output-format json 时输出到 stdout 的事件，每行一个 JSON 对象
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    /// 扫描到待处理的文件
+    ScanFound {
+        #[serde(flatten)]
+        entry: &'a FileEntry,
+    },
+    /// 一个源文件转换完成（试运行时为预计结果）
+    FileConverted {
+        #[serde(flatten)]
+        converted: &'a ConvertedFile,
+    },
+    /// 开始上传一个文件，`index` 从 1 开始
+    UploadStarted { file: &'a str, index: usize, total: usize },
+    /// 一个文件的上传结果，包括校验未通过和未上传的文件
+    UploadFinished {
+        #[serde(flatten)]
+        upload: &'a UploadReport,
+    },
+    /// 运行结束
+    RunSummary {
+        summary: &'a RunSummary,
+        duration_ms: u64,
+        error: Option<&'a str>,
+        exit_code: u8,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct EventLine<'a> {
+    timestamp: chrono::DateTime<chrono::Local>,
+    #[serde(flatten)]
+    event: Event<'a>,
+}
+
+/// 输出事件，仅在 --output-format json 时生效
+fn emit(event: Event) {
+    if !json_events() {
+        return;
+    }
+    let line = EventLine {
+        timestamp: chrono::Local::now(),
+        event,
+    };
+    match serde_json::to_string(&line) {
+        Ok(json) => println!("{}", json),
+        Err(e) => tracing::warn!("序列化事件失败: {}", e),
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct LoginRequest {
     username: String,
@@ -49,173 +142,547 @@ struct FailedFile {
     reason: String,
 }
 
+/// 运行结果，决定进程退出码
+///
+/// 作为错误的 context 使用时标记错误的类型，例如 `.context(Outcome::Config)`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// 全部成功，或没有需要上传的文件
+    Success = 0,
+    /// 其他错误，例如扫描或转换失败、网络错误
+    Error = 1,
+    /// 配置错误：转换配置、列映射或目标列定义无效（与命令行参数错误相同）
+    Config = 2,
+    /// 部分文件上传失败
+    Partial = 3,
+    /// 所有文件上传失败
+    Failed = 4,
+    /// 登录认证失败
+    Auth = 5,
+    /// `validate` 子命令有文件校验未通过
+    Invalid = 6,
+    /// 被中断（Ctrl+C）
+    Cancelled = 130,
+}
+
+impl Outcome {
+    /// 按上传结果确定退出码
+    fn of_summary(summary: &RunSummary) -> Self {
+        if summary.failed == 0 {
+            Outcome::Success
+        } else if summary.succeeded == 0 {
+            Outcome::Failed
+        } else {
+            Outcome::Partial
+        }
+    }
+
+    /// 按错误中标记的类型确定退出码，未标记时为 `Error`
+    fn of_error(error: &anyhow::Error) -> Self {
+        error.downcast_ref::<Outcome>().copied().unwrap_or(Outcome::Error)
+    }
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Success => write!(f, "成功"),
+            Outcome::Error => write!(f, "运行失败"),
+            Outcome::Config => write!(f, "配置错误"),
+            Outcome::Partial => write!(f, "部分文件上传失败"),
+            Outcome::Failed => write!(f, "所有文件上传失败"),
+            Outcome::Auth => write!(f, "认证失败"),
+            Outcome::Invalid => write!(f, "文件校验未通过"),
+            Outcome::Cancelled => write!(f, "运行已取消"),
+        }
+    }
+}
+
+impl std::error::Error for Outcome {}
+
+impl From<Outcome> for ExitCode {
+    fn from(outcome: Outcome) -> Self {
+        ExitCode::from(outcome as u8)
+    }
+}
+
 #[tokio::main]
-async fn main() -> Result<()> {
-    // 解析命令行参数
-    let args = CliArgs::parse_args();
-
-    println!("=== 专利文件上传工具 ===");
-    println!("服务器地址: {}", args.server);
-    println!("用户名: {}", args.username);
-    println!("输入目录: {}", args.input);
-    println!("输出目录: {}", args.output);
-    println!("导入模式: {}", args.import_mode);
+async fn main() -> ExitCode {
+    // 解析命令行参数（参数错误时由 clap 以退出码 2 退出）
+    let cli = CliArgs::parse_args();
+    let result = match cli.command {
+        Some(Command::Validate(validate_args)) => run_validate(validate_args).await.map(|()| Outcome::Success),
+        Some(Command::Suggest(suggest_args)) => run_suggest(suggest_args).await.map(|()| Outcome::Success),
+        Some(Command::Watch(watch_args)) => run_watch(watch_args).await.map(|()| Outcome::Success),
+        None => match cli.run {
+            Some(args) => run_upload(args).await,
+            None => Err(anyhow::anyhow!("缺少上传参数").context(Outcome::Config)),
+        },
+    };
+
+    match result {
+        Ok(outcome) => outcome.into(),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            Outcome::of_error(&e).into()
+        }
+    }
+}
+
+/// 执行上传（Ctrl+C 时中断）并写出运行报告，返回按上传结果确定的运行结果
+async fn run_upload(args: RunArgs) -> Result<Outcome> {
+    CONSOLE_FORMAT.get_or_init(|| args.output_format);
+    let started = Instant::now();
+    let mut report = new_run_report(&args);
+    let result = tokio::select! {
+        result = run(&args, &mut report) => result,
+        _ = tokio::signal::ctrl_c() => Err(anyhow::Error::new(Outcome::Cancelled)),
+    };
+    finish_run(&args, &mut report, started, result)
+}
+
+/// 补全运行报告、输出汇总事件并写出运行报告
+fn finish_run(args: &RunArgs, report: &mut RunReport, started: Instant, result: Result<()>) -> Result<Outcome> {
+    let outcome = finish_run_report(report, started, result.as_ref().err());
+    emit(Event::RunSummary {
+        summary: &report.summary,
+        duration_ms: report.duration_ms,
+        error: report.error.as_deref(),
+        exit_code: report.exit_code,
+    });
+
+    for path in &args.reports {
+        match excel::write_run_report(report, path) {
+            Ok(()) => say!("运行报告已写入: {}", path),
+            // 运行本身出错时优先返回运行的错误
+            Err(e) if result.is_ok() => anyhow::bail!("写出运行报告失败 {}: {}", path, e),
+            Err(e) => say!("✗ 写出运行报告失败 {}: {}", path, e),
+        }
+    }
+
+    result.map(|()| outcome)
+}
+
+/// 登录、扫描、转换和上传，过程中的结果记录到运行报告
+async fn run(args: &RunArgs, report: &mut RunReport) -> Result<()> {
+    say!("=== 专利文件上传工具 ===");
+    say!("服务器地址: {}", args.server);
+    say!("用户名: {}", args.username);
+    say!("输入目录: {}", args.input);
+    say!("输出目录: {}", args.output);
+    say!("导入模式: {}", args.import_mode);
     if !args.column_mappings.is_empty() {
-        println!("列名映射: {} 组", args.column_mappings.len());
+        say!("列名映射: {} 组", args.column_mappings.len());
+    }
+    if let Some(config_path) = &args.config {
+        say!("转换配置: {}", config_path);
+    }
+    if args.local_filter {
+        say!("本地过滤: 已启用");
+    }
+    if args.dry_run {
+        say!("试运行: 已启用（不写出文件，不连接服务器）");
     }
-    println!();
+    say!();
+
+    let (convert_config, schema) = prepare_convert_config(args)?;
+    let http_client = build_http_client()?;
 
-    // 创建共享的 HTTP 客户端（配置连接池和超时）
-    let http_client = reqwest::Client::builder()
-        .pool_max_idle_per_host(2)  // 限制每个主机的空闲连接数
-        .pool_idle_timeout(Duration::from_secs(30))  // 空闲连接超时
-        .timeout(Duration::from_secs(300))  // 请求超时 5 分钟
-        .connect_timeout(Duration::from_secs(10))  // 连接超时
-        .build()
-        .context("创建 HTTP 客户端失败")?;
 
     // 步骤 1: 登录获取 token
-    println!("[1/4] 正在登录...");
-    let token = login(&http_client, &args.server, &args.username, &args.password).await?;
-    println!("✓ 登录成功");
-    println!();
+    let token = if args.dry_run {
+        say!("[1/4] 试运行，跳过登录");
+        say!();
+        String::new()
+    } else {
+        say!("[1/4] 正在登录...");
+        let token = login(&http_client, &args.server, &args.username, &args.password).await?;
+        say!("✓ 登录成功");
+        say!();
+        token
+    };
 
     // 步骤 2: 扫描输入目录
-    println!("[2/4] 正在扫描输入目录...");
-    let scan_result = excel::scan_directory(&args.input)
+    say!("[2/4] 正在扫描输入目录...");
+    let scan_result = excel::scan_directory(&args.input, &convert_config.scan)
         .await
         .map_err(|e| anyhow::anyhow!(e))?;
-    println!("✓ 发现 {} 个 Excel 文件", scan_result.file_count);
-    println!();
+    say!("✓ 发现 {} 个 Excel 文件", scan_result.file_count);
+    report.files_found = scan_result.file_count;
+    for entry in &scan_result.entries {
+        emit(Event::ScanFound { entry });
+    }
+    for rejected in &scan_result.rejected_files {
+        say!("  ✗ 跳过文件: {} ({})", rejected.path, rejected.reason);
+    }
+    if !scan_result.duplicate_groups.is_empty() {
+        say!("内容相同的文件: {} 组", scan_result.duplicate_groups.len());
+        for group in &scan_result.duplicate_groups {
+            say!("  {} ({} bytes)", &group.hash[..12], group.size);
+            for (index, file) in group.files.iter().enumerate() {
+                let note = if index > 0 && convert_config.scan.skip_duplicates { " (跳过)" } else { "" };
+                say!("    {}{}", file, note);
+            }
+        }
+    }
+    say!();
+
+    if args.dry_run {
+        say!("[3/4] 正在试运行转换（不写出文件）...");
+        let needs_conversion = requires_conversion(args, &convert_config, &scan_result.entries);
+        let conversion_started = Instant::now();
+        let converted_files = excel::convert_paths(&scan_result.files, &args.input, &args.output, &convert_config)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?;
+        report.conversion_ms = Some(conversion_started.elapsed().as_millis() as u64);
+        for converted in &converted_files {
+            emit(Event::FileConverted { converted });
+        }
+        print_conversion_summary(&converted_files, &convert_config);
+        say!();
 
-    // 步骤 3: 转换文件（如果需要）
-    let files_to_upload = if !args.column_mappings.is_empty() {
-        println!("[3/4] 正在转换文件...");
+        say!("[4/4] 试运行结果");
+        let upload_url = (convert_config.output_format == OutputFormat::Xlsx)
+            .then(|| import_url(&args.server, &args.import_mode.to_string()));
+        print_dry_run(&converted_files, needs_conversion, upload_url.as_deref());
+        report.conversions = converted_files;
+        return Ok(());
+    }
+
+    // 步骤 3、4: 转换（如果需要）、校验和上传
+    process_files(args, &convert_config, schema.as_ref(), &http_client, &token, &scan_result.entries, report).await
+}
+
+/// 转换（如果需要）、校验并上传扫描到的文件，结果记录到运行报告
+async fn process_files(
+    args: &RunArgs,
+    convert_config: &ConvertConfig,
+    schema: Option<&ValidationSchema>,
+    http_client: &reqwest::Client,
+    token: &str,
+    entries: &[FileEntry],
+    report: &mut RunReport,
+) -> Result<()> {
+    let needs_conversion = requires_conversion(args, convert_config, entries);
+    let files: Vec<String> = entries.iter().map(|entry| entry.path.clone()).collect();
+
+    let files_to_upload = if needs_conversion {
+        say!("[3/4] 正在转换文件...");
         
-        // 解析列映射：仅在命令行提供参数时使用
-        let parsed = parse_column_mappings(&args.column_mappings)?;
-        println!("使用列映射:");
-        for mapping in &parsed {
-            println!("  {} -> {}", mapping.original, mapping.mapped);
+        if !convert_config.mappings.is_empty() {
+            say!("使用列映射:");
+            for mapping in &convert_config.mappings {
+                say!("  {} -> {}", mapping.original, mapping.mapped);
+            }
         }
         
-        let converted_files = excel::convert_files(&args.input, &args.output, &parsed)
+        let conversion_started = Instant::now();
+        let converted_files = excel::convert_paths(&files, &args.input, &args.output, convert_config)
             .await
             .map_err(|e| anyhow::anyhow!(e))?;
+        // 监控模式逐个文件调用，累计转换耗时
+        *report.conversion_ms.get_or_insert(0) += conversion_started.elapsed().as_millis() as u64;
+        for converted in &converted_files {
+            emit(Event::FileConverted { converted });
+        }
         
-        println!("✓ 成功转换 {} 个文件", converted_files.len());
-        println!();
+        print_conversion_summary(&converted_files, convert_config);
+        say!();
         
-        converted_files
+        // 合并文件由多个源文件共用，只上传一次
+        let mut seen = HashSet::new();
+        let outputs = converted_files
+            .iter()
+            .flat_map(|f| f.outputs.iter())
+            .filter(|output| seen.insert(output.to_string()))
+            .cloned()
+            .collect();
+        report.conversions.extend(converted_files);
+        outputs
     } else {
-        println!("[3/4] 跳过文件转换（未配置列映射）...");
-        println!("✓ 将直接上传原始文件");
-        println!();
+        say!("[3/4] 跳过文件转换（未配置列映射）...");
+        say!("✓ 将直接上传原始文件");
+        say!();
         
         // 直接使用扫描到的原文件
-        scan_result.files.clone()
+        files
     };
 
-    // 步骤 4: 上传文件
-    println!("[4/4] 正在上传文件...");
-    println!("总共 {} 个文件", files_to_upload.len());
-    println!();
-    
+    if args.check_application_numbers {
+        say!("正在校验申请号...");
+        let issues = excel::validate_application_numbers(&files_to_upload, &args.application_number_column)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?;
+        if issues.is_empty() {
+            say!("✓ 申请号校验通过");
+        } else {
+            say!("✗ 发现 {} 个无效申请号:", issues.len());
+            for issue in &issues {
+                say!("  - {} [{}] 第 {} 行: {} ({})", issue.file, issue.sheet, issue.row, issue.value, issue.message);
+            }
+        }
+        say!();
+    }
+
     let mut success_count = 0;
     let mut fail_count = 0;
     let mut failed_files: Vec<FailedFile> = Vec::new();
+
+    // 按校验规则检查，未通过的文件不上传
+    let files_to_upload = match schema {
+        Some(schema) => {
+            say!("正在按校验规则检查文件...");
+            let validation = excel::validate_files(&files_to_upload, schema, args.import_mode)
+                .await
+                .map_err(|e| anyhow::anyhow!(e))?;
+            for path in &args.validation_reports {
+                excel::write_validation_report(&validation, path).map_err(|e| anyhow::anyhow!(e))?;
+                say!("校验报告已写入: {}", path);
+            }
+            print_validation_report(&validation);
+            say!();
+
+            if args.force_upload {
+                if validation.failed_files().next().is_some() {
+                    say!("⚠ 已指定 --force-upload，校验未通过的文件仍将上传");
+                    say!();
+                }
+                files_to_upload
+            } else {
+                for failed in validation.failed_files() {
+                    fail_count += 1;
+                    let reason = format!("校验未通过 ({} 个问题)", failed.issues.len());
+                    let mut upload = upload_report(report, &failed.file, UploadStatus::Rejected);
+                    upload.error = Some(reason.clone());
+                    record_upload(report, upload);
+                    failed_files.push(FailedFile {
+                        path: failed.file.clone(),
+                        reason,
+                    });
+                }
+                validation.files.into_iter().filter(|f| f.passed).map(|f| f.file).collect()
+            }
+        }
+        None => files_to_upload,
+    };
+
+    if convert_config.output_format != OutputFormat::Xlsx {
+        say!("[4/4] 输出格式为 {}，转换结果仅供下游使用，跳过上传", convert_config.output_format.extension());
+        say!("✓ 已生成 {} 个文件，输出目录: {}", files_to_upload.len(), args.output);
+        return Ok(());
+    }
+
+    // 步骤 4: 上传文件
+    say!("[4/4] 正在上传文件...");
+    say!("总共 {} 个文件", files_to_upload.len());
+    say!();
+    
+    // 指定 --fail-fast 时，有文件失败（包括校验未通过）后其余文件不再上传
+    let mut stopped = args.fail_fast && fail_count > 0;
+    if stopped {
+        say!("✗ 有文件校验未通过，已指定 --fail-fast，停止上传");
+        say!();
+    }
+    let mut not_uploaded_count = 0;
     
     for (index, file_path) in files_to_upload.iter().enumerate() {
+        if stopped {
+            not_uploaded_count += 1;
+            let upload = upload_report(report, file_path, UploadStatus::NotUploaded);
+            record_upload(report, upload);
+            continue;
+        }
+        
         let full_path = std::fs::canonicalize(file_path)
             .unwrap_or_else(|_| Path::new(file_path).to_path_buf());
         let display_path = full_path.display().to_string();
         
         // 输出正在上传的文件信息
-        println!("[{}/{}] 正在上传: {}", index + 1, files_to_upload.len(), display_path);
+        say!("[{}/{}] 正在上传: {}", index + 1, files_to_upload.len(), display_path);
+        emit(Event::UploadStarted {
+            file: file_path,
+            index: index + 1,
+            total: files_to_upload.len(),
+        });
         
-        // 带超时的上传 (10分钟)
-        let upload_future = upload_file(
-            &http_client,
+        let upload_started = Instant::now();
+        let (uploaded, attempts) = upload_with_retries(
+            http_client,
             file_path,
             &args.server,
-            &token,
-            args.import_mode
-        );
+            token,
+            args.import_mode,
+            args.retries,
+        ).await;
+        let mut upload = upload_report(report, file_path, UploadStatus::Failed);
+        upload.attempts = attempts;
+        upload.duration_ms = upload_started.elapsed().as_millis() as u64;
         
-        match timeout(Duration::from_secs(600), upload_future).await {
-            Ok(Ok(result)) => {
-                if result.success {
-                    success_count += 1;
-                    if let Some(data) = result.data {
-                        let msg = format!("✓ 上传成功 - 总数: {}, 修改: {}, 新增: {}", 
-                            data.excel_count,
-                            data.modified_count, 
-                            data.upserted_count
-                        );
-                        println!("{}", msg);
-                    } else {
-                        println!("✓ 上传成功");
-                    }
-                    
-                    // 上传成功后等待3秒再继续下一个
-                    if index + 1 < files_to_upload.len() {
-                        println!();
-                        tokio::time::sleep(Duration::from_secs(3)).await;
-                    }
+        match uploaded {
+            Ok(result) if result.success => {
+                success_count += 1;
+                upload.status = UploadStatus::Succeeded;
+                if let Some(data) = result.data {
+                    let msg = format!("✓ 上传成功 - 总数: {}, 修改: {}, 新增: {}", 
+                        data.excel_count,
+                        data.modified_count, 
+                        data.upserted_count
+                    );
+                    say!("{}", msg);
+                    upload.modified_count = Some(data.modified_count.into());
+                    upload.upserted_count = Some(data.upserted_count.into());
+                    upload.excel_count = Some(data.excel_count.into());
                 } else {
-                    fail_count += 1;
-                    let reason = result.message.unwrap_or_else(|| "未知错误".to_string());
-                    let msg = format!("✗ 上传失败: {}", reason);
-                    println!("{}", msg);
-                    println!();
-                    failed_files.push(FailedFile {
-                        path: display_path,
-                        reason,
-                    });
+                    say!("✓ 上传成功");
+                }
+                record_upload(report, upload);
+                
+                // 上传成功后等待3秒再继续下一个
+                if index + 1 < files_to_upload.len() {
+                    say!();
+                    tokio::time::sleep(Duration::from_secs(3)).await;
                 }
             }
-            Ok(Err(e)) => {
-                fail_count += 1;
-                let reason = e.to_string();
-                let msg = format!("✗ 上传失败: {}", reason);
-                println!("{}", msg);
-                println!();
-                failed_files.push(FailedFile {
-                    path: display_path,
-                    reason,
-                });
-            }
-            Err(_) => {
+            uploaded => {
                 fail_count += 1;
-                let reason = "上传超时 (10分钟)".to_string();
+                let reason = match uploaded {
+                    Ok(result) => result.message.unwrap_or_else(|| "未知错误".to_string()),
+                    Err(e) => e.to_string(),
+                };
                 let msg = format!("✗ 上传失败: {}", reason);
-                println!("{}", msg);
-                println!();
+                say!("{}", msg);
+                say!();
+                upload.error = Some(reason.clone());
+                record_upload(report, upload);
                 failed_files.push(FailedFile {
                     path: display_path,
                     reason,
                 });
+                
+                if args.fail_fast && index + 1 < files_to_upload.len() {
+                    stopped = true;
+                    say!("✗ 已指定 --fail-fast，其余 {} 个文件不再上传", files_to_upload.len() - index - 1);
+                    say!();
+                }
             }
         }
     }
     
-    println!();
-    println!("=== 上传完成 ===");
-    println!("成功: {} 个文件", success_count);
-    println!("失败: {} 个文件", fail_count);
+    say!();
+    say!("=== 上传完成 ===");
+    say!("成功: {} 个文件", success_count);
+    say!("失败: {} 个文件", fail_count);
+    if not_uploaded_count > 0 {
+        say!("未上传: {} 个文件", not_uploaded_count);
+    }
     
     // 输出失败文件列表
     if !failed_files.is_empty() {
-        println!();
-        println!("失败的文件:");
+        say!();
+        say!("失败的文件:");
         for failed in &failed_files {
-            println!("  - {} (原因: {})", failed.path, failed.reason);
+            say!("  - {} (原因: {})", failed.path, failed.reason);
         }
     }
 
     Ok(())
 }
 
+/// 按转换配置文件和命令行参数生成转换配置，并应用目标列定义
+fn prepare_convert_config(args: &RunArgs) -> Result<(ConvertConfig, Option<ValidationSchema>)> {
+    // 读取转换配置，命令行传入的列映射追加在配置文件之后
+    let mut convert_config = match &args.config {
+        Some(path) => excel::load_convert_config(path)
+            .map_err(|e| anyhow::anyhow!(e))
+            .context(Outcome::Config)?,
+        None => ConvertConfig::default(),
+    };
+    convert_config
+        .mappings
+        .extend(parse_column_mappings(&args.column_mappings).context(Outcome::Config)?);
+    convert_config.dry_run = args.dry_run;
+    for column in &args.required_columns {
+        if !convert_config.required_columns.contains(column) {
+            convert_config.required_columns.push(column.clone());
+        }
+    }
+    if args.local_filter {
+        // 本地过滤使用与上传相同的导入模式，列名等设置沿用配置文件
+        convert_config.import_filter.get_or_insert_with(Default::default).mode = args.import_mode;
+    }
+    convert_config.scan.hash |= args.hash_files;
+    convert_config.scan.skip_duplicates |= args.skip_duplicate_files;
+    convert_config.scan.include.extend(args.include.iter().cloned());
+    convert_config.scan.exclude.extend(args.exclude.iter().cloned());
+    if args.max_depth.is_some() {
+        convert_config.scan.max_depth = args.max_depth;
+    }
+    convert_config.scan.include_hidden |= args.include_hidden;
+    convert_config.scan.follow_symlinks |= args.follow_symlinks;
+    if let Some(format) = args.target_format {
+        convert_config.output_format = format;
+    }
+    convert_config.scan.archives |= args.archives || args.nested_archives;
+    convert_config.scan.nested_archives |= args.nested_archives;
+    if let Some(max_rows) = args.consolidate_rows {
+        convert_config.consolidate.get_or_insert_with(Default::default).max_rows = max_rows;
+    }
+    // 目标列定义：迁移旧版本的列名、检查列映射、按别名添加映射并确定必需列
+    let schema = match &args.schema {
+        Some(path) => {
+            let schema = excel::load_schema(path)
+                .map_err(|e| anyhow::anyhow!(e))
+                .context(Outcome::Config)?;
+            let check = excel::apply_schema(&schema, &mut convert_config, args.import_mode);
+            print_schema_check(path, &schema, &check);
+            Some(schema)
+        }
+        None => None,
+    };
+    if args.split_rows.is_some() || args.split_size.is_some() {
+        let split = convert_config.split.get_or_insert_with(Default::default);
+        if args.split_rows.is_some() {
+            split.max_rows = args.split_rows;
+        }
+        if args.split_size.is_some() {
+            split.max_megabytes = args.split_size;
+        }
+    }
+    // 在扫描和转换之前发现无效的正则、脚本、列值转换和拆分上限，按配置错误退出
+    excel::check_convert_config(&convert_config)
+        .map_err(|e| anyhow::anyhow!(e))
+        .context(Outcome::Config)?;
+
+    Ok((convert_config, schema))
+}
+
+/// 创建共享的 HTTP 客户端（配置连接池和超时）
+fn build_http_client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .pool_max_idle_per_host(2)  // 限制每个主机的空闲连接数
+        .pool_idle_timeout(Duration::from_secs(30))  // 空闲连接超时
+        .timeout(Duration::from_secs(300))  // 请求超时 5 分钟
+        .connect_timeout(Duration::from_secs(10))  // 连接超时
+        .build()
+        .context("创建 HTTP 客户端失败")
+}
+
+/// 是否需要先转换再上传
+fn requires_conversion(args: &RunArgs, convert_config: &ConvertConfig, entries: &[FileEntry]) -> bool {
+    // CSV/TSV 输入需要先转换为 xlsx 才能上传
+    let has_text_inputs = entries
+        .iter()
+        .any(|entry| matches!(entry.format, FileFormat::Csv | FileFormat::Tsv));
+    has_text_inputs
+        || args.config.is_some()
+        || args.local_filter
+        || !convert_config.mappings.is_empty()
+        || convert_config.split.is_some()
+        || convert_config.consolidate.is_some()
+        || !convert_config.required_columns.is_empty()
+        || convert_config.output_format != OutputFormat::Xlsx
+}
+
 /// 登录并获取 token
 async fn login(client: &reqwest::Client, server_url: &str, username: &str, password: &str) -> Result<String> {
     let login_url = format!("{}/auth/admin/login", server_url.trim_end_matches('/'));
@@ -232,8 +699,14 @@ async fn login(client: &reqwest::Client, server_url: &str, username: &str, passw
         .await
         .context("发送登录请求失败")?;
     
-    if !response.status().is_success() {
-        anyhow::bail!("登录失败: HTTP {}", response.status());
+    let status = response.status();
+    if !status.is_success() {
+        let error = anyhow::anyhow!("登录失败: HTTP {}", status);
+        // 账号或密码错误、无权限时服务器返回 401/403，其他状态码按服务器错误处理
+        if matches!(status, reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN) {
+            return Err(error.context(Outcome::Auth));
+        }
+        return Err(error);
     }
     
     let result: LoginResponse = response.json().await.context("解析登录响应失败")?;
@@ -241,10 +714,72 @@ async fn login(client: &reqwest::Client, server_url: &str, username: &str, passw
     if result.success && result.data.is_some() {
         Ok(result.data.unwrap().token)
     } else {
-        anyhow::bail!("登录失败: {}", result.message.unwrap_or_else(|| "未知错误".to_string()));
+        Err(anyhow::anyhow!("登录失败: {}", result.message.unwrap_or_else(|| "未知错误".to_string()))
+            .context(Outcome::Auth))
+    }
+}
+
+/// 上传单个文件（超时 10 分钟），遇到暂时性错误时最多重试 `retries` 次，返回结果和请求次数
+///
+/// 只有超时、连接或发送请求失败和 HTTP 5xx 会重试；文件不存在、HTTP 4xx、响应无法解析
+/// 以及服务器返回 `success: false` 时不重试。
+async fn upload_with_retries(
+    client: &reqwest::Client,
+    file_path: &str,
+    server_url: &str,
+    token: &str,
+    import_mode: ImportMode,
+    retries: u32,
+) -> (Result<UploadResult>, u32) {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let upload_future = upload_file(client, file_path, server_url, token, import_mode);
+        let result = match timeout(Duration::from_secs(600), upload_future).await {
+            Ok(result) => result,
+            Err(elapsed) => Err(anyhow::Error::new(elapsed).context("上传超时 (10分钟)")),
+        };
+        match result {
+            Err(e) if attempts <= retries && is_transient(&e) => {
+                say!("  第 {} 次上传失败: {}，{} 秒后重试", attempts, e, RETRY_DELAY.as_secs());
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+            result => return (result, attempts),
+        }
     }
 }
 
+/// 是否为重试可能成功的暂时性错误：超时、连接或发送请求失败、HTTP 5xx
+fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if cause.is::<tokio::time::error::Elapsed>() {
+            return true;
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return e.is_timeout() || e.is_connect() || e.is_request();
+        }
+        if let Some(e) = cause.downcast_ref::<HttpError>() {
+            return e.status.is_server_error();
+        }
+        false
+    })
+}
+
+/// 服务器返回的错误状态
+#[derive(Debug)]
+struct HttpError {
+    status: reqwest::StatusCode,
+    message: String,
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "上传失败 [{}]: {}", self.status, self.message)
+    }
+}
+
+impl std::error::Error for HttpError {}
+
 /// 上传单个文件
 async fn upload_file(
     client: &reqwest::Client,
@@ -254,12 +789,12 @@ async fn upload_file(
     import_mode: ImportMode,
 ) -> Result<UploadResult> {
     let path = Path::new(file_path);
-    if !path.exists() {
+    if !Path::new(excel::archive::physical_path(file_path)).exists() {
         anyhow::bail!("文件不存在: {}", file_path);
     }
     
-    // 读取文件内容
-    let file_content = tokio::fs::read(&path)
+    // 读取文件内容（压缩包内的文件直接从压缩包读取）
+    let file_content = excel::archive::read(file_path)
         .await
         .context("读取文件失败")?;
     
@@ -280,11 +815,7 @@ async fn upload_file(
         );
     
     // 构建上传 URL
-    let upload_url = format!(
-        "{}/admin/patent/import?importMode={}",
-        server_url.trim_end_matches('/'),
-        import_mode
-    );
+    let upload_url = import_url(server_url, &import_mode.to_string());
     
     // 发送请求
     let response = client
@@ -297,8 +828,8 @@ async fn upload_file(
     
     let status = response.status();
     if !status.is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "无法获取错误信息".to_string());
-        anyhow::bail!("上传失败 [{}]: {}", status, error_text);
+        let message = response.text().await.unwrap_or_else(|_| "无法获取错误信息".to_string());
+        return Err(HttpError { status, message }.into());
     }
     
     // 解析响应
@@ -307,6 +838,789 @@ async fn upload_file(
     Ok(result)
 }
 
+/// 运行开始时的报告，结束时由 `finish_run_report` 补全
+fn new_run_report(args: &RunArgs) -> RunReport {
+    let now = chrono::Local::now();
+    RunReport {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        started_at: now,
+        finished_at: now,
+        duration_ms: 0,
+        server: args.server.clone(),
+        username: args.username.clone(),
+        input: args.input.clone(),
+        output: args.output.clone(),
+        import_mode: args.import_mode,
+        dry_run: args.dry_run,
+        files_found: 0,
+        conversion_ms: None,
+        conversions: Vec::new(),
+        uploads: Vec::new(),
+        summary: RunSummary::default(),
+        error: None,
+        exit_code: Outcome::Success as u8,
+    }
+}
+
+/// 记录结束时间和中止运行的错误，汇总上传结果并确定运行结果
+fn finish_run_report(report: &mut RunReport, started: Instant, error: Option<&anyhow::Error>) -> Outcome {
+    report.finished_at = chrono::Local::now();
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    report.error = error.map(|e| format!("{:#}", e));
+
+    let mut summary = RunSummary::default();
+    for upload in &report.uploads {
+        match upload.status {
+            UploadStatus::Succeeded => summary.succeeded += 1,
+            UploadStatus::Failed | UploadStatus::Rejected => summary.failed += 1,
+            UploadStatus::NotUploaded => summary.not_uploaded += 1,
+        }
+        summary.modified_count += upload.modified_count.unwrap_or(0);
+        summary.upserted_count += upload.upserted_count.unwrap_or(0);
+        summary.excel_count += upload.excel_count.unwrap_or(0);
+    }
+
+    let outcome = match error {
+        Some(error) => Outcome::of_error(error),
+        None => Outcome::of_summary(&summary),
+    };
+    report.summary = summary;
+    report.exit_code = outcome as u8;
+    outcome
+}
+
+/// 单个文件的上传结果，计数、请求次数、耗时和错误由调用方填写
+fn upload_report(report: &RunReport, file: &str, status: UploadStatus) -> UploadReport {
+    UploadReport {
+        file: file.to_string(),
+        sources: upload_sources(report, file),
+        status,
+        modified_count: None,
+        upserted_count: None,
+        excel_count: None,
+        attempts: 0,
+        duration_ms: 0,
+        error: None,
+    }
+}
+
+/// 记录一个文件的上传结果并输出事件
+fn record_upload(report: &mut RunReport, upload: UploadReport) {
+    emit(Event::UploadFinished { upload: &upload });
+    report.uploads.push(upload);
+}
+
+/// 生成待上传文件的源文件，直接上传的文件为其本身
+fn upload_sources(report: &RunReport, file: &str) -> Vec<String> {
+    let sources: Vec<String> = report
+        .conversions
+        .iter()
+        .filter(|converted| converted.outputs.iter().any(|output| output == file))
+        .map(|converted| converted.source.clone())
+        .collect();
+    if sources.is_empty() {
+        vec![file.to_string()]
+    } else {
+        sources
+    }
+}
+
+/// 输出转换结果：跳过的工作表、行错误、重复专利、合并和过滤情况
+fn print_conversion_summary(converted_files: &[ConvertedFile], config: &ConvertConfig) {
+    let skipped = converted_files.iter().filter(|converted| converted.skipped.is_some()).count();
+    say!("✓ 成功转换 {} 个文件", converted_files.len() - skipped);
+    for converted in converted_files {
+        if let Some(reason) = &converted.skipped {
+            say!("  跳过文件: {} ({})", converted.source, reason);
+        }
+        for skipped in &converted.skipped_sheets {
+            say!("  跳过工作表: {} [{}] ({})", converted.source, skipped.sheet, skipped.reason);
+        }
+        if converted.script_skipped_rows > 0 {
+            say!("  脚本跳过: {} ({} 行)", converted.source, converted.script_skipped_rows);
+        }
+        if converted.outputs.len() > 1 && converted.merged_rows.is_empty() {
+            say!("  拆分输出: {} ({} 个分卷)", converted.source, converted.outputs.len());
+        }
+        for error in &converted.row_errors {
+            say!("  ✗ 行错误: {} [{}] 第 {} 行: {}", converted.source, error.sheet, error.row, error.message);
+        }
+        // 未配置列映射时所有列都按原名输出，不逐一列出
+        if !config.mappings.is_empty() && !converted.unmapped_headers.is_empty() {
+            say!("  未映射的列: {} ({})", converted.source, converted.unmapped_headers.join(", "));
+        }
+        if !converted.unmatched_mappings.is_empty() {
+            let mappings: Vec<String> = converted
+                .unmatched_mappings
+                .iter()
+                .map(|m| format!("{} -> {}", m.original, m.mapped))
+                .collect();
+            say!("  ✗ 列映射未匹配: {} ({})", converted.source, mappings.join(", "));
+        }
+        for sheet in converted.sheets.iter().filter(|sheet| !sheet.missing_columns.is_empty()) {
+            say!("  ✗ 缺少必需列: {} [{}] ({})", converted.source, sheet.name, sheet.missing_columns.join(", "));
+        }
+    }
+    if config.duplicates.is_some() {
+        let duplicates: Vec<_> = converted_files
+            .iter()
+            .flat_map(|f| f.duplicate_rows.iter().map(move |row| (&f.source, row)))
+            .collect();
+        let removed = duplicates.iter().filter(|(_, row)| row.removed).count();
+        say!("重复专利: {} 行, 已移除 {} 行", duplicates.len(), removed);
+        for (source, row) in duplicates {
+            say!(
+                "  {} {} [{}] 第 {} 行，保留 {} [{}] 第 {} 行{}",
+                row.key,
+                source,
+                row.sheet,
+                row.row,
+                row.kept.file,
+                row.kept.sheet,
+                row.kept.row,
+                if row.removed { " (已移除)" } else { "" }
+            );
+        }
+    }
+    if config.consolidate.is_some() {
+        let mut merged: Vec<(&String, Vec<&String>)> = Vec::new();
+        for converted in converted_files {
+            for range in &converted.merged_rows {
+                match merged.iter_mut().find(|(output, _)| *output == &range.output) {
+                    Some((_, sources)) if sources.contains(&&converted.source) => {}
+                    Some((_, sources)) => sources.push(&converted.source),
+                    None => merged.push((&range.output, vec![&converted.source])),
+                }
+            }
+        }
+        say!("合并小文件: {} 个合并文件", merged.len());
+        for (output, sources) in merged {
+            say!("  {} <- {} 个源文件", output, sources.len());
+            for source in sources {
+                say!("    {}", source);
+            }
+        }
+    }
+    if let Some(filter) = &config.import_filter {
+        let kept: usize = converted_files.iter().map(|f| f.rows_written).sum();
+        let dropped: usize = converted_files.iter().map(|f| f.filtered_rows).sum();
+        say!("本地过滤 ({}): 保留 {} 行, 丢弃 {} 行", filter.mode, kept, dropped);
+        for converted in converted_files.iter().filter(|f| f.filtered_rows > 0) {
+            say!("  {}: 保留 {} 行, 丢弃 {} 行", converted.source, converted.rows_written, converted.filtered_rows);
+        }
+    }
+}
+
+/// 输出试运行结果：每个文件的工作表、行数、列映射、输出路径和上传地址
+fn print_dry_run(converted_files: &[ConvertedFile], needs_conversion: bool, upload_url: Option<&str>) {
+    for converted in converted_files {
+        say!("{}", converted.source);
+        for sheet in &converted.sheets {
+            say!("  工作表 {}: {} 行", sheet.name, sheet.rows);
+            if !sheet.mapped_headers.is_empty() {
+                let mapped: Vec<String> = sheet
+                    .mapped_headers
+                    .iter()
+                    .map(|m| format!("{} -> {}", m.original, m.mapped))
+                    .collect();
+                say!("    已映射: {}", mapped.join(", "));
+            }
+            if !sheet.unmapped_headers.is_empty() {
+                say!("    未映射: {}", sheet.unmapped_headers.join(", "));
+            }
+        }
+        if converted.filtered_rows > 0 {
+            say!("  过滤: {} 行", converted.filtered_rows);
+        }
+        if needs_conversion {
+            for output in &converted.outputs {
+                say!("  输出: {}", output);
+            }
+        } else {
+            say!("  输出: 不转换，直接上传原始文件");
+        }
+        match upload_url {
+            Some(url) => say!("  上传: POST {}", url),
+            None => say!("  上传: 输出格式不是 xlsx，不上传"),
+        }
+    }
+
+    let rows: usize = converted_files.iter().map(|f| f.rows_written).sum();
+    say!();
+    say!("=== 试运行完成 ===");
+    say!("文件: {} 个, 数据行: {} 行", converted_files.len(), rows);
+    say!("未写出任何文件，未连接服务器");
+}
+
+/// 监控状态：每个已处理的源文件的结果，重启后跳过已完成且未变化的文件
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WatchState {
+    files: BTreeMap<String, WatchedFile>,
+}
+
+/// 已处理的源文件
+#[derive(Debug, Serialize, Deserialize)]
+struct WatchedFile {
+    /// 处理时的文件大小和修改时间，变化后会重新处理
+    size: u64,
+    modified: Option<chrono::DateTime<chrono::Local>>,
+    status: WatchStatus,
+    processed_at: chrono::DateTime<chrono::Local>,
+    /// 上传的文件（转换后的文件或源文件本身）
+    uploads: Vec<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WatchStatus {
+    Completed,
+    Failed,
+}
+
+/// 本次监控中处理失败、等待重试的文件
+struct RetryingFile {
+    /// 失败时的文件大小和修改时间，变化后按新文件等待写入完成
+    size: u64,
+    modified: Option<chrono::DateTime<chrono::Local>>,
+    /// 连续失败的次数
+    failures: u32,
+    /// 下次重试的时间
+    retry_at: Instant,
+}
+
+/// 第 `failures` 次失败后重试前的等待时间，从 `WATCH_RETRY_BACKOFF` 开始每次翻倍，最长为 `WATCH_MAX_RETRY_BACKOFF`
+fn watch_retry_backoff(failures: u32) -> Duration {
+    WATCH_RETRY_BACKOFF
+        .saturating_mul(1 << failures.saturating_sub(1).min(16))
+        .min(WATCH_MAX_RETRY_BACKOFF)
+}
+
+/// 等待写入完成的文件
+struct PendingFile {
+    size: u64,
+    modified: Option<chrono::DateTime<chrono::Local>>,
+    /// 最近一次观察到大小或修改时间变化的时间
+    since: Instant,
+}
+
+/// 监控子命令：定期扫描输入目录，文件写入完成后转换并上传，结果记录到状态文件
+///
+/// 文件的大小和修改时间在 `settle` 秒内不变时视为写入完成；先写入临时文件再改名的文件在改名后才会被扫描到。
+/// 同一批文件逐个转换和上传，一个文件失败不影响其他文件；失败的文件按 `watch_retry_backoff` 的间隔重试，
+/// 文件变化后按新文件重新等待写入完成。
+async fn run_watch(args: Box<WatchArgs>) -> Result<()> {
+    let WatchArgs { run: args, state, interval, settle } = *args;
+    CONSOLE_FORMAT.get_or_init(|| args.output_format);
+    if args.dry_run {
+        return Err(anyhow::anyhow!("watch 子命令不支持 --dry-run").context(Outcome::Config));
+    }
+    let state_path = state.unwrap_or_else(|| Path::new(&args.output).join(WATCH_STATE_FILE).display().to_string());
+    let mut state = load_watch_state(&state_path).context(Outcome::Config)?;
+    let settle = Duration::from_secs(settle);
+
+    say!("=== 监控输入目录 ===");
+    say!("服务器地址: {}", args.server);
+    say!("用户名: {}", args.username);
+    say!("输入目录: {}", args.input);
+    say!("输出目录: {}", args.output);
+    say!("导入模式: {}", args.import_mode);
+    say!("状态文件: {} (已记录 {} 个文件)", state_path, state.files.len());
+    say!("扫描间隔: {} 秒，写入完成判断: {} 秒内不变", interval, settle.as_secs());
+    say!();
+
+    let (convert_config, schema) = prepare_convert_config(&args)?;
+    // 监控模式逐个处理文件，跨文件的重复检测和合并只在单个源文件内生效
+    if convert_config.duplicates.is_some() {
+        say!("⚠ 监控模式下逐个处理文件，duplicates 只检测同一源文件内的重复专利，不跨文件检测");
+    }
+    if convert_config.consolidate.is_some() {
+        say!("⚠ 监控模式下逐个处理文件，consolidate 不会合并不同的源文件");
+    }
+    let http_client = build_http_client()?;
+
+    // 启动时先登录一次，账号或服务器地址错误时立即退出
+    login(&http_client, &args.server, &args.username, &args.password).await?;
+    say!("✓ 登录成功，开始监控（Ctrl+C 停止）");
+    say!();
+
+    let mut pending: HashMap<String, PendingFile> = HashMap::new();
+    // 本次监控中处理失败、等待重试的文件
+    let mut failed: HashMap<String, RetryingFile> = HashMap::new();
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval.max(1)));
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => {
+                say!("已停止监控");
+                return Err(anyhow::Error::new(Outcome::Cancelled));
+            }
+        }
+
+        let scan_result = match excel::scan_directory(&args.input, &convert_config.scan).await {
+            Ok(scan_result) => scan_result,
+            Err(e) => {
+                say!("✗ 扫描输入目录失败: {}", e);
+                continue;
+            }
+        };
+
+        // 跳过已完成且未变化的文件；失败后未变化的文件到重试时间后直接处理，其余文件等待写入完成
+        let now = Instant::now();
+        let mut ready = Vec::new();
+        let mut seen = HashSet::new();
+        for entry in scan_result.entries {
+            seen.insert(entry.path.clone());
+            let completed = state.files.get(&entry.path).is_some_and(|file| {
+                file.status == WatchStatus::Completed && file.size == entry.size && file.modified == entry.modified
+            });
+            if completed {
+                pending.remove(&entry.path);
+                continue;
+            }
+            match failed.get(&entry.path) {
+                Some(file) if file.size == entry.size && file.modified == entry.modified => {
+                    pending.remove(&entry.path);
+                    if now >= file.retry_at {
+                        say!("重试第 {} 次失败的文件: {}", file.failures, entry.path);
+                        ready.push(entry);
+                    }
+                    continue;
+                }
+                Some(_) => {
+                    failed.remove(&entry.path);
+                }
+                None => {}
+            }
+
+            let file = pending.entry(entry.path.clone()).or_insert_with(|| PendingFile {
+                size: entry.size,
+                modified: entry.modified,
+                since: now,
+            });
+            if file.size != entry.size || file.modified != entry.modified {
+                file.size = entry.size;
+                file.modified = entry.modified;
+                file.since = now;
+            }
+            if now.duration_since(file.since) >= settle {
+                pending.remove(&entry.path);
+                ready.push(entry);
+            }
+        }
+        // 已删除或已改名的文件不再等待或重试
+        pending.retain(|path, _| seen.contains(path));
+        failed.retain(|path, _| seen.contains(path));
+
+        if ready.is_empty() {
+            continue;
+        }
+
+        say!("=== {} 发现 {} 个已写入完成的文件 ===", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), ready.len());
+        for entry in &ready {
+            say!("  {}", entry.path);
+            emit(Event::ScanFound { entry });
+        }
+        say!();
+
+        let started = Instant::now();
+        let mut report = new_run_report(&args);
+        report.files_found = ready.len();
+        let batch = async {
+            // 每批重新登录，避免长时间监控后 token 过期
+            let token = login(&http_client, &args.server, &args.username, &args.password).await?;
+            // 逐个文件转换和上传，一个文件无法转换或校验时不影响同批的其他文件
+            for entry in &ready {
+                let processed = process_files(
+                    &args,
+                    &convert_config,
+                    schema.as_ref(),
+                    &http_client,
+                    &token,
+                    std::slice::from_ref(entry),
+                    &mut report,
+                )
+                .await;
+                if let Err(e) = processed {
+                    say!("✗ 处理失败 {}: {:#}", entry.path, e);
+                    let mut upload = upload_report(&report, &entry.path, UploadStatus::Failed);
+                    upload.error = Some(format!("{:#}", e));
+                    record_upload(&mut report, upload);
+                }
+            }
+            Ok(())
+        };
+        let result = tokio::select! {
+            result = batch => result,
+            _ = tokio::signal::ctrl_c() => Err(anyhow::Error::new(Outcome::Cancelled)),
+        };
+        let cancelled = result.as_ref().is_err_and(|e| Outcome::of_error(e) == Outcome::Cancelled);
+        if let Err(e) = &result {
+            if !cancelled {
+                say!("✗ 处理失败: {:#}", e);
+            }
+        }
+        let error = result.as_ref().err().map(|e| format!("{:#}", e));
+        // 运行报告写出失败不影响监控
+        if let Err(e) = finish_run(&args, &mut report, started, result) {
+            if !cancelled {
+                say!("✗ {:#}", e);
+            }
+        }
+        if cancelled {
+            // 中断的这批文件不记录，重新启动后再处理
+            return Err(anyhow::Error::new(Outcome::Cancelled));
+        }
+
+        for entry in &ready {
+            let file = watched_file(entry, &report, error.as_deref());
+            match file.status {
+                WatchStatus::Completed => {
+                    failed.remove(&entry.path);
+                }
+                WatchStatus::Failed => {
+                    let failures = failed.get(&entry.path).map_or(0, |file| file.failures) + 1;
+                    let backoff = watch_retry_backoff(failures);
+                    say!("  {} 将在 {} 秒后重试", entry.path, backoff.as_secs());
+                    failed.insert(
+                        entry.path.clone(),
+                        RetryingFile {
+                            size: entry.size,
+                            modified: entry.modified,
+                            failures,
+                            retry_at: Instant::now() + backoff,
+                        },
+                    );
+                }
+            }
+            state.files.insert(entry.path.clone(), file);
+        }
+        save_watch_state(&state_path, &state)?;
+        say!();
+    }
+}
+
+/// 按运行报告确定源文件的处理结果：生成的文件全部上传成功（或无需上传）时为已完成
+fn watched_file(entry: &FileEntry, report: &RunReport, error: Option<&str>) -> WatchedFile {
+    let uploads: Vec<&UploadReport> = report
+        .uploads
+        .iter()
+        .filter(|upload| upload.sources.contains(&entry.path))
+        .collect();
+    let error = error.map(str::to_string).or_else(|| {
+        uploads
+            .iter()
+            .find(|upload| upload.status != UploadStatus::Succeeded)
+            .map(|upload| upload.error.clone().unwrap_or_else(|| "未上传".to_string()))
+    });
+
+    WatchedFile {
+        size: entry.size,
+        modified: entry.modified,
+        status: if error.is_none() { WatchStatus::Completed } else { WatchStatus::Failed },
+        processed_at: chrono::Local::now(),
+        // 转换或校验出错的源文件记为失败但没有发出请求，不算作上传的文件
+        uploads: uploads
+            .iter()
+            .filter(|upload| upload.status != UploadStatus::Failed || upload.attempts > 0)
+            .map(|upload| upload.file.clone())
+            .collect(),
+        error,
+    }
+}
+
+fn load_watch_state(path: &str) -> Result<WatchState> {
+    if !Path::new(path).exists() {
+        return Ok(WatchState::default());
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("读取状态文件失败: {}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("解析状态文件失败: {}", path))
+}
+
+/// 先写入临时文件再改名，避免中断时留下不完整的状态文件
+fn save_watch_state(path: &str, state: &WatchState) -> Result<()> {
+    if let Some(parent) = Path::new(path).parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).with_context(|| format!("创建目录失败: {}", parent.display()))?;
+    }
+    let temp_path = format!("{}.tmp", path);
+    std::fs::write(&temp_path, serde_json::to_string_pretty(state)?)
+        .with_context(|| format!("写入状态文件失败: {}", temp_path))?;
+    std::fs::rename(&temp_path, path).with_context(|| format!("写入状态文件失败: {}", path))?;
+    Ok(())
+}
+
+/// 校验子命令：检查目录中的文件，不连接服务器
+async fn run_validate(args: ValidateArgs) -> Result<()> {
+    say!("=== 文件校验 ===");
+    say!("输入目录: {}", args.input);
+    say!("校验规则: {}", args.schema);
+    say!("导入模式: {}", args.import_mode);
+    say!();
+
+    let schema = excel::load_schema(&args.schema)
+        .map_err(|e| anyhow::anyhow!(e))
+        .context(Outcome::Config)?;
+    let files = excel::get_excel_files(&args.input)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    say!("发现 {} 个 Excel 文件", files.len());
+    say!();
+
+    let report = excel::validate_files(&files, &schema, args.import_mode)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    for path in &args.reports {
+        excel::write_validation_report(&report, path).map_err(|e| anyhow::anyhow!(e))?;
+        say!("校验报告已写入: {}", path);
+    }
+    print_validation_report(&report);
+
+    let failed = report.failed_files().count();
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} 个文件校验未通过", failed).context(Outcome::Invalid));
+    }
+
+    Ok(())
+}
+
+/// 输出校验结果，每个文件最多列出前 20 个问题
+fn print_validation_report(report: &ValidationReport) {
+    const MAX_ISSUES_PER_FILE: usize = 20;
+
+    for file in &report.files {
+        if file.passed {
+            say!("✓ {} ({} 行)", file.file, file.rows_checked);
+            continue;
+        }
+
+        say!("✗ {} ({} 个问题)", file.file, file.issues.len());
+        for issue in file.issues.iter().take(MAX_ISSUES_PER_FILE) {
+            say!("  - [{}] 第 {} 行 {}: {} ({})", issue.sheet, issue.row, issue.column, issue.value, issue.message);
+        }
+        if file.issues.len() > MAX_ISSUES_PER_FILE {
+            say!("  ... 还有 {} 个问题，详见校验报告", file.issues.len() - MAX_ISSUES_PER_FILE);
+        }
+    }
+}
+
+/// 列映射建议子命令：统计表头并给出映射建议，按需写入转换配置文件
+async fn run_suggest(args: SuggestArgs) -> Result<()> {
+    say!("=== 列映射建议 ===");
+    say!("输入目录: {}", args.input);
+
+    excel::check_min_score(args.min_score)
+        .map_err(|e| anyhow::anyhow!(e))
+        .context(Outcome::Config)?;
+    let schema = match &args.schema {
+        Some(path) => Some(
+            excel::load_schema(path)
+                .map_err(|e| anyhow::anyhow!(e))
+                .context(Outcome::Config)?,
+        ),
+        None => None,
+    };
+    let mut targets: Vec<&String> = args.targets.iter().collect();
+    for rule in schema.iter().flat_map(|schema| schema.all_rules()) {
+        if !targets.contains(&&rule.name) {
+            targets.push(&rule.name);
+        }
+    }
+    if targets.is_empty() {
+        anyhow::bail!("请通过 --target 或 --schema 指定目标列");
+    }
+    say!("目标列: {} 个", targets.len());
+    say!();
+
+    let files = excel::get_excel_files(&args.input)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    say!("发现 {} 个 Excel 文件", files.len());
+
+    let result = excel::suggest_mappings(&files, &args.targets, schema.as_ref(), args.min_score)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    print_suggestions(&result);
+
+    let mappings = if args.interactive {
+        choose_mappings(&result.suggestions)?
+    } else {
+        excel::best_mappings(&result.suggestions)
+    };
+
+    say!();
+    if mappings.is_empty() {
+        say!("没有需要添加的列映射");
+        return Ok(());
+    }
+    say!("采纳的列映射:");
+    for mapping in &mappings {
+        say!("  {} -> {}", mapping.original, mapping.mapped);
+    }
+
+    match &args.write {
+        Some(path) => {
+            write_mappings(path, &mappings, schema.as_ref().map(|schema| schema.version))?;
+            say!("列映射已写入: {}", path);
+        }
+        None => say!("使用 --write <FILE> 将列映射写入转换配置文件"),
+    }
+
+    Ok(())
+}
+
+/// 输出表头统计和按相似度排列的建议
+fn print_suggestions(result: &MappingSuggestions) {
+    for failed in &result.failed_files {
+        say!("  ✗ 无法读取: {} ({})", failed.path, failed.reason);
+    }
+    for failed in &result.failed_sheets {
+        say!("  ✗ 无法读取工作表: {} [{}] ({})", failed.file, failed.sheet, failed.reason);
+    }
+    say!("共 {} 个不同的表头", result.headers.len());
+    say!();
+
+    say!("映射建议:");
+    for suggestion in &result.suggestions {
+        say!(
+            "  {} -> {} ({:.2}, {}, {} 个工作表)",
+            suggestion.original,
+            suggestion.mapped,
+            suggestion.score,
+            match_kind_label(suggestion.match_kind),
+            suggestion.frequency
+        );
+    }
+    if !result.unmatched.is_empty() {
+        say!("没有候选的表头: {}", result.unmatched.join(", "));
+    }
+}
+
+/// 逐个表头选择候选目标列，已与目标列同名的表头不再询问
+fn choose_mappings(suggestions: &[MappingSuggestion]) -> Result<Vec<ColumnMapping>> {
+    let mut headers: Vec<(&str, Vec<&MappingSuggestion>)> = Vec::new();
+    for suggestion in suggestions {
+        match headers.iter_mut().find(|(header, _)| *header == suggestion.original) {
+            Some((_, candidates)) => candidates.push(suggestion),
+            None => headers.push((&suggestion.original, vec![suggestion])),
+        }
+    }
+
+    let mut mappings = Vec::new();
+    let mut used_targets: HashSet<&str> = HashSet::new();
+    let total = headers.len();
+    for (index, (header, candidates)) in headers.into_iter().enumerate() {
+        if candidates[0].match_kind == MatchKind::Exact {
+            used_targets.insert(&candidates[0].mapped);
+            continue;
+        }
+
+        say!();
+        say!("[{}/{}] {} (出现在 {} 个工作表)", index + 1, total, header, candidates[0].frequency);
+        for (number, candidate) in candidates.iter().enumerate() {
+            let note = if used_targets.contains(candidate.mapped.as_str()) { "，已被其他列使用" } else { "" };
+            say!(
+                "  {}) {} ({:.2}, {}{})",
+                number + 1,
+                candidate.mapped,
+                candidate.score,
+                match_kind_label(candidate.match_kind),
+                note
+            );
+        }
+
+        let choice = loop {
+            print!("选择 [1-{}，回车选 1，n 跳过，q 结束]: ", candidates.len());
+            std::io::stdout().flush()?;
+            let mut input = String::new();
+            if std::io::stdin().read_line(&mut input)? == 0 {
+                break None;
+            }
+            match input.trim() {
+                "" => break Some(Some(candidates[0])),
+                "n" | "N" => break Some(None),
+                "q" | "Q" => break None,
+                number => match number.parse::<usize>() {
+                    Ok(number) if (1..=candidates.len()).contains(&number) => break Some(Some(candidates[number - 1])),
+                    _ => say!("无效的选择: {}", number),
+                },
+            }
+        };
+
+        match choice {
+            Some(Some(candidate)) => {
+                used_targets.insert(&candidate.mapped);
+                mappings.push(ColumnMapping {
+                    original: candidate.original.clone(),
+                    mapped: candidate.mapped.clone(),
+                });
+            }
+            Some(None) => {}
+            None => break,
+        }
+    }
+
+    Ok(mappings)
+}
+
+fn match_kind_label(kind: MatchKind) -> &'static str {
+    match kind {
+        MatchKind::Exact => "完全相同",
+        MatchKind::Alias => "别名",
+        MatchKind::Normalized => "规范化后相同",
+        MatchKind::Pinyin => "拼音匹配",
+        MatchKind::Fuzzy => "相似",
+    }
+}
+
+/// 将列映射写入转换配置文件，同名原列的映射被替换，其他配置保持不变
+/// 按目标列定义生成建议时同时记录定义的版本
+fn write_mappings(path: &str, mappings: &[ColumnMapping], schema_version: Option<u32>) -> Result<()> {
+    let mut config = match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str::<serde_json::Value>(&content)
+            .with_context(|| format!("解析转换配置文件失败: {}", path))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_json::json!({}),
+        Err(e) => return Err(e).with_context(|| format!("读取转换配置文件失败: {}", path)),
+    };
+
+    let object = config
+        .as_object_mut()
+        .with_context(|| format!("转换配置文件不是 JSON 对象: {}", path))?;
+    let mut existing: Vec<ColumnMapping> = match object.get("mappings") {
+        Some(value) => serde_json::from_value(value.clone())
+            .with_context(|| format!("解析转换配置文件中的 mappings 失败: {}", path))?,
+        None => Vec::new(),
+    };
+    existing.retain(|m| !mappings.iter().any(|new| new.original == m.original));
+    existing.extend(mappings.iter().cloned());
+    object.insert("mappings".to_string(), serde_json::to_value(&existing)?);
+    if let Some(version) = schema_version {
+        object.insert("schema_version".to_string(), version.into());
+    }
+
+    std::fs::write(path, serde_json::to_string_pretty(&config)?)
+        .with_context(|| format!("写入转换配置文件失败: {}", path))
+}
+
+/// 输出按目标列定义处理转换配置的结果
+fn print_schema_check(path: &str, schema: &ValidationSchema, check: &SchemaCheck) {
+    say!("目标列定义: {} (版本 {})", path, schema.version);
+    if !check.migrated.is_empty() {
+        say!("  转换配置按旧版本编写，已迁移列名:");
+        for rename in &check.migrated {
+            say!("    {} -> {}", rename.from, rename.to);
+        }
+    }
+    if !check.alias_mappings.is_empty() {
+        say!("  按别名添加 {} 个列映射", check.alias_mappings.len());
+    }
+    for issue in &check.issues {
+        say!("  ⚠ {}", issue);
+    }
+    say!();
+}
+
 /// 解析命令行传入的列映射参数
 /// 格式: "原列名:映射列名"
 fn parse_column_mappings(mappings: &[String]) -> Result<Vec<ColumnMapping>> {
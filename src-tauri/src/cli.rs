@@ -1,7 +1,10 @@
-use clap::{Parser, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use crate::commands::types::OutputFormat;
 
 /// 导入模式
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum ImportMode {
     /// 上传所有专利
     #[default]
@@ -24,10 +27,110 @@ impl std::fmt::Display for ImportMode {
     }
 }
 
+/// 控制台输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ConsoleFormat {
+    /// 输出便于阅读的进度信息
+    #[default]
+    Text,
+    /// 每个事件输出一行 JSON 到 stdout，进度信息输出到 stderr
+    Json,
+}
+
 /// 专利文件上传命令行工具
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct CliArgs {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// 不指定子命令时执行登录、扫描、转换和上传
+    #[command(flatten)]
+    pub run: Option<RunArgs>,
+}
+
+/// 子命令
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// 按校验规则检查目录中的 Excel 文件，不连接服务器
+    Validate(ValidateArgs),
+    /// 统计目录中文件的表头，给出到目标列的列映射建议，不连接服务器
+    Suggest(SuggestArgs),
+    /// 持续监控输入目录，文件写入完成后转换并上传，已完成的文件记录在状态文件中
+    Watch(Box<WatchArgs>),
+}
+
+/// 校验参数
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+    /// 待校验的目录（通常为转换后的输出目录）
+    #[arg(short, long)]
+    pub input: String,
+
+    /// 目标列定义文件（JSON 格式）
+    #[arg(long, value_name = "FILE")]
+    pub schema: String,
+
+    /// 导入模式，决定启用哪些按模式配置的规则
+    #[arg(short = 'v', long, default_value_t = ImportMode::All, value_name = "MODE")]
+    pub import_mode: ImportMode,
+
+    /// 校验报告输出路径（.json 或 .xlsx，可多次指定）
+    #[arg(long = "report", value_name = "PATH")]
+    pub reports: Vec<String>,
+}
+
+/// 列映射建议参数
+#[derive(Args, Debug)]
+pub struct SuggestArgs {
+    /// 源文件所在的目录
+    #[arg(short, long)]
+    pub input: String,
+
+    /// 目标列名（可多次指定）
+    #[arg(short = 't', long = "target", value_name = "COLUMN")]
+    pub targets: Vec<String>,
+
+    /// 使用目标列定义文件（JSON 格式）中的列名和别名作为目标列
+    #[arg(long, value_name = "FILE")]
+    pub schema: Option<String>,
+
+    /// 最低相似度（0 到 1），低于该值的候选不列出
+    #[arg(long, default_value_t = crate::excel::DEFAULT_MIN_SCORE, value_name = "SCORE")]
+    pub min_score: f64,
+
+    /// 逐个表头确认或拒绝建议
+    #[arg(long)]
+    pub interactive: bool,
+
+    /// 将采纳的映射写入转换配置文件，文件已存在时更新其中的 mappings
+    #[arg(long, value_name = "FILE")]
+    pub write: Option<String>,
+}
+
+/// 监控参数
+#[derive(Args, Debug)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub run: RunArgs,
+
+    /// 状态文件，记录已处理的文件，重启后不再处理已完成的文件（默认: 输出目录下的 .patent-cli-watch.json）
+    #[arg(long, value_name = "FILE")]
+    pub state: Option<String>,
+
+    /// 扫描输入目录的间隔（秒）
+    #[arg(long, default_value_t = 10, value_name = "SECONDS")]
+    pub interval: u64,
+
+    /// 文件大小和修改时间保持不变多久（秒）后视为写入完成
+    #[arg(long, default_value_t = 10, value_name = "SECONDS")]
+    pub settle: u64,
+}
+
+/// 上传参数
+#[derive(Args, Debug)]
+pub struct RunArgs {
     /// 服务器地址 (例如: http://localhost:3000)
     #[arg(short, long)]
     pub server: String,
@@ -56,6 +159,110 @@ pub struct CliArgs {
     /// 例如: --column-mapping "申请号:申请号" --column-mapping "名称:专利名称"
     #[arg(short = 'm', long = "column-mapping", value_name = "MAPPING")]
     pub column_mappings: Vec<String>,
+
+    /// 转换配置文件（JSON 格式，可配置列映射、工作表选择等转换规则）
+    #[arg(short = 'c', long = "config", value_name = "FILE")]
+    pub config: Option<String>,
+
+    /// 转换后必须存在的列（映射后的列名，可多次指定），缺少时在转换结果中列出
+    #[arg(long = "required-column", value_name = "COLUMN")]
+    pub required_columns: Vec<String>,
+
+    /// 按导入模式在本地过滤数据行，减少上传的数据量（需要进行转换）
+    #[arg(long)]
+    pub local_filter: bool,
+
+    /// 上传前校验申请号（格式、专利类型和校验位），并输出无效申请号所在的行
+    #[arg(long)]
+    pub check_application_numbers: bool,
+
+    /// 申请号所在的列名（映射后）
+    #[arg(long, default_value = "申请号", value_name = "COLUMN")]
+    pub application_number_column: String,
+
+    /// 目标列定义文件（JSON 格式）：按别名映射列名、检查列映射，上传前校验文件，未通过的文件不会上传
+    #[arg(long, value_name = "FILE")]
+    pub schema: Option<String>,
+
+    /// 校验报告输出路径（.json 或 .xlsx，可多次指定）
+    #[arg(long = "validation-report", value_name = "PATH")]
+    pub validation_reports: Vec<String>,
+
+    /// 校验未通过时仍然上传
+    #[arg(long)]
+    pub force_upload: bool,
+
+    /// 计算文件内容哈希，报告内容相同的文件
+    #[arg(long)]
+    pub hash_files: bool,
+
+    /// 内容相同的文件只转换和上传一个（隐含 --hash-files）
+    #[arg(long)]
+    pub skip_duplicate_files: bool,
+
+    /// 仅扫描匹配的文件（glob，相对于输入目录，可多次指定）
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// 排除匹配的文件或目录（glob，相对于输入目录，可多次指定）
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// 最大递归深度，输入目录下的文件深度为 1
+    #[arg(long)]
+    pub max_depth: Option<usize>,
+
+    /// 扫描隐藏的文件和目录
+    #[arg(long)]
+    pub include_hidden: bool,
+
+    /// 跟随符号链接进入目录
+    #[arg(long)]
+    pub follow_symlinks: bool,
+
+    /// 读取 .zip 压缩包中的 Excel 文件
+    #[arg(long)]
+    pub archives: bool,
+
+    /// 继续展开压缩包中的压缩包（隐含 --archives）
+    #[arg(long)]
+    pub nested_archives: bool,
+
+    /// 转换输出格式；csv/tsv 仅供下游使用，不会上传
+    #[arg(long, value_enum)]
+    pub target_format: Option<OutputFormat>,
+
+    /// 输出文件每个分卷最多的数据行数，超过后拆分为 name.part-001.xlsx 等多个文件
+    #[arg(long, value_name = "ROWS")]
+    pub split_rows: Option<usize>,
+
+    /// 输出文件每个分卷最多的数据量（MB，按单元格文本估算）
+    #[arg(long, value_name = "MB")]
+    pub split_size: Option<f64>,
+
+    /// 将表头兼容的小文件合并为最多 ROWS 行的文件后再上传
+    #[arg(long, value_name = "ROWS")]
+    pub consolidate_rows: Option<usize>,
+
+    /// 运行报告输出路径（.json、.xlsx 或 .csv，可多次指定），包含每个文件的转换和上传结果
+    #[arg(long = "report", value_name = "PATH")]
+    pub reports: Vec<String>,
+
+    /// 超时、网络错误或服务器 5xx 错误时的重试次数（HTTP 4xx、响应无法解析或服务器返回失败时不重试）
+    #[arg(long, default_value_t = 0, value_name = "N")]
+    pub retries: u32,
+
+    /// 控制台输出格式：text / json（每个事件一行 JSON，供自动化流程读取）
+    #[arg(long, value_enum, default_value_t = ConsoleFormat::Text, value_name = "FORMAT")]
+    pub output_format: ConsoleFormat,
+
+    /// 遇到第一个失败的文件（包括校验未通过）后停止上传，其余文件不再上传
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// 试运行：扫描并在内存中转换，输出每个文件的预计结果，不写出文件也不连接服务器
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 impl CliArgs {
@@ -1,13 +1,17 @@
 use tauri::command;
-use crate::commands::types::{ColumnMapping, ScanResult};
+use crate::cli::ImportMode;
+use crate::commands::types::{
+    ColumnMapping, ConvertConfig, ConvertedFile, DuplicateReport, FileEntry, MappingPreview, MappingSuggestions,
+    ScanOptions, ScanResult, SchemaCheck, ValidationIssue, ValidationReport, ValidationSchema, WorkbookPreview,
+};
 use crate::excel;
 
 /// 扫描 Excel 文件
 #[command]
-pub async fn scan_excel_files(source_path: String) -> Result<ScanResult, String> {
+pub async fn scan_excel_files(source_path: String, options: Option<ScanOptions>) -> Result<ScanResult, String> {
     tracing::info!("开始扫描目录: {}", source_path);
     
-    excel::scan_directory(&source_path)
+    excel::scan_directory(&source_path, &options.unwrap_or_default())
         .await
         .map_err(|e| {
             tracing::error!("扫描文件失败: {}", e);
@@ -15,16 +19,100 @@ pub async fn scan_excel_files(source_path: String) -> Result<ScanResult, String>
         })
 }
 
-/// 转换 Excel 文件
+/// 读取文件的工作表、表头和行数
+#[command]
+pub async fn inspect_excel_files(files: Vec<String>, mappings: Vec<ColumnMapping>) -> Result<Vec<FileEntry>, String> {
+    tracing::info!("开始读取文件信息，文件数: {}", files.len());
+
+    excel::inspect_files(&files, &mappings)
+        .await
+        .map_err(|e| {
+            tracing::error!("读取文件信息失败: {}", e);
+            e.to_string()
+        })
+}
+
+/// 预览工作簿：工作表列表、表头和前若干行数据
+#[command]
+pub async fn preview_workbook(path: String, sheet: Option<String>, limit: Option<usize>) -> Result<WorkbookPreview, String> {
+    tracing::info!("预览工作簿: {}", path);
+
+    excel::preview_workbook(&path, sheet.as_deref(), limit.unwrap_or(excel::DEFAULT_PREVIEW_ROWS))
+        .await
+        .map_err(|e| {
+            tracing::error!("预览工作簿失败: {}", e);
+            e.to_string()
+        })
+}
+
+/// 预览前若干行按当前列映射和转换规则处理后的结果
+#[command]
+pub async fn preview_mapping(
+    path: String,
+    sheet: Option<String>,
+    limit: Option<usize>,
+    mappings: Vec<ColumnMapping>,
+    config: Option<ConvertConfig>,
+) -> Result<MappingPreview, String> {
+    tracing::info!("预览列映射: {}, 映射数量: {}", path, mappings.len());
+
+    let mut config = config.unwrap_or_default();
+    config.mappings.extend(mappings);
+
+    excel::preview_mapping(&path, sheet.as_deref(), limit.unwrap_or(excel::DEFAULT_PREVIEW_ROWS), &config)
+        .await
+        .map_err(|e| {
+            tracing::error!("预览列映射失败: {}", e);
+            e.to_string()
+        })
+}
+
+/// 统计文件的表头，给出到目标列的列映射建议
+#[command]
+pub async fn suggest_mappings(
+    files: Vec<String>,
+    targets: Vec<String>,
+    schema: Option<ValidationSchema>,
+    min_score: Option<f64>,
+) -> Result<MappingSuggestions, String> {
+    tracing::info!("开始生成列映射建议，文件数: {}, 目标列数: {}", files.len(), targets.len());
+
+    excel::suggest_mappings(&files, &targets, schema.as_ref(), min_score.unwrap_or(excel::DEFAULT_MIN_SCORE))
+        .await
+        .map_err(|e| {
+            tracing::error!("生成列映射建议失败: {}", e);
+            e.to_string()
+        })
+}
+
+/// 转换 Excel 文件，试运行时只返回预计的结果，不写出文件
 #[command]
 pub async fn convert_excel_files(
     source_path: String,
     target_path: String,
     mappings: Vec<ColumnMapping>,
-) -> Result<Vec<String>, String> {
+    config: Option<ConvertConfig>,
+    dry_run: Option<bool>,
+    schema: Option<ValidationSchema>,
+    import_mode: Option<ImportMode>,
+) -> Result<Vec<ConvertedFile>, String> {
     tracing::info!("开始转换文件: {} -> {}, 映射数量: {}", source_path, target_path, mappings.len());
     
-    excel::convert_files(&source_path, &target_path, &mappings)
+    let mut config = config.unwrap_or_default();
+    config.mappings.extend(mappings);
+    config.dry_run = dry_run.unwrap_or(false);
+    if let Some(schema) = &schema {
+        let check = excel::apply_schema(schema, &mut config, import_mode.unwrap_or_default());
+        if !check.issues.is_empty() {
+            tracing::warn!(
+                "按目标列定义检查转换配置发现 {} 个问题，继续转换: {}",
+                check.issues.len(),
+                check.issues.join("; ")
+            );
+        }
+    }
+
+    excel::convert_files(&source_path, &target_path, &config)
         .await
         .map_err(|e| {
             tracing::error!("转换文件失败: {}", e);
@@ -32,6 +120,102 @@ pub async fn convert_excel_files(
         })
 }
 
+/// 检测多个文件之间重复的专利
+#[command]
+pub async fn find_duplicate_patents(
+    files: Vec<String>,
+    mappings: Vec<ColumnMapping>,
+    config: Option<ConvertConfig>,
+) -> Result<DuplicateReport, String> {
+    tracing::info!("开始检测重复专利，文件数: {}", files.len());
+
+    let mut config = config.unwrap_or_default();
+    config.mappings.extend(mappings);
+
+    excel::find_duplicates(&files, &config)
+        .await
+        .map_err(|e| {
+            tracing::error!("检测重复专利失败: {}", e);
+            e.to_string()
+        })
+}
+
+/// 校验文件中的申请号
+#[command]
+pub async fn validate_application_numbers(
+    files: Vec<String>,
+    column: String,
+) -> Result<Vec<ValidationIssue>, String> {
+    excel::validate_application_numbers(&files, &column)
+        .await
+        .map_err(|e| {
+            tracing::error!("校验申请号失败: {}", e);
+            e.to_string()
+        })
+}
+
+/// 按校验规则检查文件，并按需写出报告（.json / .xlsx）
+#[command]
+pub async fn validate_files(
+    files: Vec<String>,
+    schema: ValidationSchema,
+    import_mode: ImportMode,
+    report_paths: Option<Vec<String>>,
+) -> Result<ValidationReport, String> {
+    tracing::info!("开始校验文件，文件数: {}, 导入模式: {}", files.len(), import_mode);
+
+    let report = excel::validate_files(&files, &schema, import_mode)
+        .await
+        .map_err(|e| {
+            tracing::error!("校验文件失败: {}", e);
+            e.to_string()
+        })?;
+
+    for path in report_paths.unwrap_or_default() {
+        excel::write_validation_report(&report, &path).map_err(|e| {
+            tracing::error!("写出校验报告失败: {}", e);
+            e.to_string()
+        })?;
+    }
+
+    Ok(report)
+}
+
+/// 读取目标列定义文件
+#[command]
+pub async fn load_schema(path: String) -> Result<ValidationSchema, String> {
+    tracing::info!("读取目标列定义: {}", path);
+
+    excel::load_schema(&path).map_err(|e| {
+        tracing::error!("读取目标列定义失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 读取转换配置文件，配置中的相对路径按配置文件所在目录解析
+#[command]
+pub async fn load_convert_config(path: String) -> Result<ConvertConfig, String> {
+    tracing::info!("读取转换配置: {}", path);
+
+    excel::load_convert_config(&path).map_err(|e| {
+        tracing::error!("读取转换配置失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 按目标列定义检查列映射，返回迁移的列名、按别名添加的映射和发现的问题
+#[command]
+pub async fn check_mappings(
+    mappings: Vec<ColumnMapping>,
+    config: Option<ConvertConfig>,
+    schema: ValidationSchema,
+    import_mode: Option<ImportMode>,
+) -> Result<SchemaCheck, String> {
+    let mut config = config.unwrap_or_default();
+    config.mappings.extend(mappings);
+    Ok(excel::apply_schema(&schema, &mut config, import_mode.unwrap_or_default()))
+}
+
 /// 获取转换后的文件列表
 #[command]
 pub async fn get_converted_files(target_path: String) -> Result<Vec<String>, String> {
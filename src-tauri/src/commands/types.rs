@@ -1,10 +1,103 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use clap::ValueEnum;
+use crate::cli::ImportMode;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScanResult {
     pub file_count: usize,
     pub total_size: u64,
     pub files: Vec<String>,
+    /// 与 `files` 一一对应的文件信息
+    pub entries: Vec<FileEntry>,
+    /// 内容相同的文件分组，仅在启用哈希时检测
+    pub duplicate_groups: Vec<DuplicateFileGroup>,
+    /// 扩展名是 Excel 但内容不是工作簿的文件
+    pub rejected_files: Vec<RejectedFile>,
+}
+
+/// 被拒绝的文件
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RejectedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+/// 扫描到的单个文件
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileEntry {
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<chrono::DateTime<chrono::Local>>,
+    pub format: FileFormat,
+    /// 工作表信息，未读取工作簿时为 None
+    pub sheets: Option<Vec<SheetInfo>>,
+    /// 读取工作簿失败的原因
+    pub error: Option<String>,
+}
+
+/// 文件格式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileFormat {
+    Xlsx,
+    Xls,
+    Csv,
+    Tsv,
+    Unknown,
+}
+
+/// 工作表概况
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SheetInfo {
+    pub name: String,
+    /// 表头所在行（从 1 开始），空工作表为 None
+    pub header_row: Option<usize>,
+    pub headers: Vec<String>,
+    /// 表头以下的行数，可能包含空行
+    pub estimated_rows: usize,
+    /// 表头是否包含所有列映射的原列名（或映射后的列名），未提供列映射或读取失败时为 None
+    pub headers_match: Option<bool>,
+    /// 读取工作表失败的原因，此时表头和行数为空
+    pub error: Option<String>,
+}
+
+/// 扫描选项
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// 计算文件内容哈希，找出内容相同的文件
+    pub hash: bool,
+    /// 内容相同的文件只处理第一个（隐含 `hash`）
+    pub skip_duplicates: bool,
+    /// 读取每个文件的工作表、表头和行数
+    pub inspect: bool,
+    /// 用于判断表头是否匹配的列映射
+    pub mappings: Vec<ColumnMapping>,
+    /// 仅保留匹配的文件（glob，相对于扫描目录，为空时不限制）
+    pub include: Vec<String>,
+    /// 排除匹配的文件或目录（glob，相对于扫描目录）
+    pub exclude: Vec<String>,
+    /// 最大递归深度，扫描目录下的文件深度为 1
+    pub max_depth: Option<usize>,
+    /// 扫描隐藏的文件和目录
+    pub include_hidden: bool,
+    /// 跟随符号链接
+    pub follow_symlinks: bool,
+    /// 读取 .zip 压缩包中的 Excel 文件
+    pub archives: bool,
+    /// 继续展开压缩包中的压缩包（需同时启用 `archives`）
+    pub nested_archives: bool,
+}
+
+/// 内容相同的一组文件
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateFileGroup {
+    /// 文件内容的 SHA-256（十六进制）
+    pub hash: String,
+    pub size: u64,
+    /// 按扫描顺序排列，第一个为保留的文件
+    pub files: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -19,4 +112,799 @@ pub struct ConvertProgress {
     pub progress: f32,
     pub total_files: usize,
     pub completed_files: usize,
-}
\ No newline at end of file
+}
+
+/// 转换配置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ConvertConfig {
+    /// 列名映射
+    pub mappings: Vec<ColumnMapping>,
+    /// 工作表选择规则
+    pub sheets: SheetSelection,
+    /// 列值转换规则，按映射后的列名匹配
+    pub transforms: Vec<ColumnTransform>,
+    /// 值对照表，供 `lookup` 转换引用
+    pub lookup_tables: HashMap<String, HashMap<String, String>>,
+    /// 追加的计算列，按顺序计算
+    pub computed_columns: Vec<ComputedColumn>,
+    /// 行处理脚本，在列值转换和计算列之后执行
+    pub script: Option<ScriptConfig>,
+    /// 按导入模式在本地过滤数据行
+    pub import_filter: Option<ImportFilter>,
+    /// 跨文件的重复专利检测
+    pub duplicates: Option<DuplicateConfig>,
+    /// 扫描源目录时的选项
+    pub scan: ScanOptions,
+    /// 输出格式
+    pub output_format: OutputFormat,
+    /// CSV/TSV 输出的编码
+    pub text_encoding: TextEncoding,
+    /// 将过大的输出拆分为多个文件
+    pub split: Option<SplitConfig>,
+    /// 将表头兼容的小文件合并输出
+    pub consolidate: Option<ConsolidateConfig>,
+    /// 试运行：只在内存中转换并返回预计的结果，不写出任何文件
+    ///
+    /// 只对单次运行有效，由命令行的 `--dry-run` 或界面的试运行参数设置，不从配置文件读取也不写入配置文件。
+    #[serde(skip)]
+    pub dry_run: bool,
+    /// 转换后必须存在的列（映射后的列名），缺少时在转换结果中列出
+    pub required_columns: Vec<String>,
+    /// 编写该配置时目标列定义的版本，用于迁移列名
+    pub schema_version: Option<u32>,
+}
+
+/// 转换输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    #[default]
+    Xlsx,
+    Csv,
+    Tsv,
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Xlsx => "xlsx",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+        }
+    }
+
+    /// CSV/TSV 的分隔符
+    pub fn delimiter(self) -> u8 {
+        match self {
+            OutputFormat::Tsv => b'\t',
+            _ => b',',
+        }
+    }
+}
+
+/// CSV/TSV 输出的编码
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    /// UTF-8，文件开头写入 BOM，Excel 打开时能正确识别中文
+    #[default]
+    Utf8Bom,
+    /// 不带 BOM 的 UTF-8
+    Utf8,
+}
+
+/// 输出拆分规则，任一上限达到即开始新的分卷，每个分卷都重复表头
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SplitConfig {
+    /// 每个分卷最多的数据行数
+    pub max_rows: Option<usize>,
+    /// 每个分卷最多的数据量（MB，按单元格文本估算，压缩后的 xlsx 文件通常更小）
+    pub max_megabytes: Option<f64>,
+}
+
+impl SplitConfig {
+    /// 检查拆分上限，上限为 0 或负数时每行都会单独成为一个分卷
+    pub fn check(&self) -> Result<(), String> {
+        if self.max_rows == Some(0) {
+            return Err("拆分行数上限必须大于 0".to_string());
+        }
+        if let Some(megabytes) = self.max_megabytes {
+            if !(megabytes.is_finite() && megabytes > 0.0) {
+                return Err(format!("拆分大小上限必须大于 0: {}", megabytes));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 小文件合并规则
+///
+/// 映射后列名相同（不区分顺序和大小写）的工作表写入同一个合并文件，达到行数上限后开始新的合并文件。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ConsolidateConfig {
+    /// 每个合并文件最多的数据行数，数据行数超过该值的源文件单独输出
+    pub max_rows: usize,
+    /// 合并文件所在的目录名和文件名前缀，输出为 `merged/merged-20240101-120000-001.xlsx` 等
+    pub name: String,
+}
+
+impl Default for ConsolidateConfig {
+    fn default() -> Self {
+        Self {
+            max_rows: 50000,
+            name: "merged".to_string(),
+        }
+    }
+}
+
+/// 工作表选择规则
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SheetSelection {
+    /// 选择模式
+    pub mode: SheetSelectionMode,
+    /// 仅保留匹配的工作表（为空时不限制）
+    pub include: Vec<NamePattern>,
+    /// 排除匹配的工作表
+    pub exclude: Vec<NamePattern>,
+    /// `required_columns` 模式下表头（映射后）必须包含的列
+    pub required_columns: Vec<String>,
+}
+
+/// 工作表选择模式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SheetSelectionMode {
+    /// 转换所有工作表
+    #[default]
+    All,
+    /// 仅转换第一个工作表
+    FirstOnly,
+    /// 仅转换表头包含全部必需列的工作表
+    RequiredColumns,
+}
+
+/// 名称匹配规则
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum NamePattern {
+    /// 名称完全相同
+    Name(String),
+    /// 正则表达式匹配
+    Regex(String),
+}
+
+/// 单列的值转换流水线
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ColumnTransform {
+    /// 映射后的列名
+    pub column: String,
+    /// 依次执行的转换步骤
+    pub steps: Vec<Transform>,
+}
+
+/// 内置的值转换
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Transform {
+    /// 去除首尾空白
+    Trim,
+    /// 去除前缀（不区分大小写），例如申请号的 "CN"
+    StripPrefix { prefix: String },
+    /// 转换为 ISO 日期（YYYY-MM-DD），支持 "2023.05.01"、"2023年5月1日" 及 Excel 日期序列号
+    IsoDate,
+    /// 拆分多值单元格，去除空白后用统一分隔符重新连接
+    Split {
+        #[serde(default = "default_split_separators")]
+        separators: Vec<String>,
+        #[serde(default = "default_split_join")]
+        join: String,
+    },
+    /// 按对照表替换值，未命中时使用 `default`，未配置 `default` 则保留原值
+    Lookup {
+        table: String,
+        #[serde(default)]
+        default: Option<String>,
+    },
+}
+
+fn default_split_separators() -> Vec<String> {
+    [";", "；", "、", "\n"].iter().map(|s| s.to_string()).collect()
+}
+
+fn default_split_join() -> String {
+    ";".to_string()
+}
+
+/// 计算列
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ComputedColumn {
+    /// 列名，与已有列同名时覆盖该列的值
+    pub name: String,
+    /// 列值来源
+    pub value: ComputedValue,
+}
+
+/// 计算列的值来源
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ComputedValue {
+    /// 固定值
+    Constant { value: String },
+    /// 源文件名
+    FileName,
+    /// 工作表名
+    SheetName,
+    /// 模板，`{列名}` 替换为同一行该列的值，`{{`、`}}` 表示字面的花括号
+    Template { template: String },
+}
+
+/// 行处理脚本配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScriptConfig {
+    /// Rhai 脚本文件路径，脚本需定义 `fn transform(row)`
+    pub path: String,
+    /// 脚本执行出错时的处理方式
+    #[serde(default)]
+    pub on_error: ScriptErrorAction,
+}
+
+/// 脚本出错时的处理方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptErrorAction {
+    /// 保留原始行
+    #[default]
+    Keep,
+    /// 丢弃该行
+    Skip,
+    /// 终止当前文件的转换
+    Abort,
+}
+
+/// 本地导入过滤配置，与服务器的导入模式规则保持一致
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ImportFilter {
+    /// 导入模式
+    pub mode: ImportMode,
+    /// 申请号列，未配置专利类型列时从申请号推断专利类型
+    pub application_number_column: String,
+    /// 专利类型列
+    pub type_column: Option<String>,
+    /// 专利类型列中表示发明专利的值
+    pub invention_values: Vec<String>,
+    /// 法律状态列
+    pub legal_status_column: String,
+    /// 法律状态列中表示有效的值
+    pub valid_values: Vec<String>,
+}
+
+impl Default for ImportFilter {
+    fn default() -> Self {
+        Self {
+            mode: ImportMode::All,
+            application_number_column: "申请号".to_string(),
+            type_column: None,
+            invention_values: ["发明", "发明专利", "发明申请", "发明授权", "invention"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            legal_status_column: "法律状态".to_string(),
+            valid_values: ["有效", "valid"].iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+/// 重复专利检测配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DuplicateConfig {
+    /// 识别同一专利的列（映射后），按顺序取第一个非空值
+    pub key_columns: Vec<String>,
+    /// 转换时是否移除重复行
+    pub remove: bool,
+    /// 移除重复行时保留哪一行
+    pub keep: DuplicateWinner,
+}
+
+impl Default for DuplicateConfig {
+    fn default() -> Self {
+        Self {
+            key_columns: vec!["申请号".to_string(), "公开号".to_string()],
+            remove: false,
+            keep: DuplicateWinner::default(),
+        }
+    }
+}
+
+/// 重复行的保留规则
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateWinner {
+    /// 保留修改时间最新的文件中的行
+    #[default]
+    LatestFile,
+    /// 保留非空单元格最多的行，相同时取最新的文件
+    MostComplete,
+    /// 保留扫描顺序中最先出现的行
+    First,
+}
+
+/// 行位置
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RowLocation {
+    pub file: String,
+    pub sheet: String,
+    /// 源文件中的行号（从 1 开始）
+    pub row: usize,
+}
+
+/// 同一专利出现的所有位置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateGroup {
+    pub key: String,
+    pub locations: Vec<RowLocation>,
+    /// 按保留规则选出的行在 `locations` 中的下标
+    pub kept: usize,
+}
+
+/// 重复专利报告
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+    /// 未被保留的重复行数
+    pub duplicate_rows: usize,
+    /// 无法读取、未参与重复检测的工作表
+    pub failed_sheets: Vec<FailedSheet>,
+}
+
+/// 文件中未被保留的重复行
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateRow {
+    pub sheet: String,
+    pub row: usize,
+    pub key: String,
+    /// 是否已在转换时移除
+    pub removed: bool,
+    /// 同组中保留的行
+    pub kept: RowLocation,
+}
+
+/// 行级错误
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RowError {
+    pub sheet: String,
+    /// 源文件中的行号（从 1 开始）
+    pub row: usize,
+    pub message: String,
+}
+
+/// 校验发现的问题
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidationIssue {
+    pub file: String,
+    pub sheet: String,
+    /// 源文件中的行号（从 1 开始）
+    pub row: usize,
+    pub column: String,
+    pub value: String,
+    pub message: String,
+}
+
+/// 目标列定义：服务器期望的列、列的别名和上传前的校验规则
+///
+/// 同一份定义用于检查列映射、按别名自动映射和上传前校验。列名变更时递增 `version`，
+/// 并在 `migrations` 中记录变更，按旧版本编写的转换配置可以据此迁移。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ValidationSchema {
+    /// 定义的版本
+    pub version: u32,
+    /// 所有导入模式通用的列规则
+    pub columns: Vec<ColumnRule>,
+    /// 各导入模式额外的列规则
+    pub modes: HashMap<ImportMode, Vec<ColumnRule>>,
+    /// 各版本的列名变更
+    pub migrations: Vec<SchemaMigration>,
+}
+
+impl ValidationSchema {
+    /// 指定导入模式下生效的全部列规则
+    pub fn rules_for(&self, mode: ImportMode) -> Vec<ColumnRule> {
+        let mut rules = self.columns.clone();
+        if let Some(extra) = self.modes.get(&mode) {
+            rules.extend(extra.iter().cloned());
+        }
+        rules
+    }
+
+    /// 所有导入模式下的全部列规则，同名的列可能出现多次
+    pub fn all_rules(&self) -> impl Iterator<Item = &ColumnRule> {
+        self.columns.iter().chain(self.modes.values().flatten())
+    }
+}
+
+/// 某个版本的列名变更
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SchemaMigration {
+    /// 引入变更的版本
+    pub version: u32,
+    pub renames: Vec<ColumnRename>,
+}
+
+/// 列名变更
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ColumnRename {
+    pub from: String,
+    pub to: String,
+}
+
+/// 按目标列定义处理转换配置的结果
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SchemaCheck {
+    /// 迁移时应用的列名变更
+    pub migrated: Vec<ColumnRename>,
+    /// 按别名添加的列映射
+    pub alias_mappings: Vec<ColumnMapping>,
+    /// 列映射和列名迁移的问题，例如映射到未定义的列、行处理脚本中无法自动迁移的列名
+    pub issues: Vec<String>,
+}
+
+/// 单列的校验规则
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ColumnRule {
+    /// 列名（映射后）
+    pub name: String,
+    /// 列必须存在
+    #[serde(default)]
+    pub required: bool,
+    /// 值不能为空
+    #[serde(default)]
+    pub not_empty: bool,
+    /// 值类型
+    #[serde(default, rename = "type")]
+    pub value_type: ValueType,
+    /// `date` 类型的日期格式（chrono 格式），默认为 `%Y-%m-%d`
+    #[serde(default)]
+    pub date_format: Option<String>,
+    /// 允许的取值，为空时不限制
+    #[serde(default)]
+    pub allowed_values: Vec<String>,
+    /// 源文件中可能使用的其他列名，转换时自动映射为 `name`
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// 列值类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueType {
+    #[default]
+    Text,
+    Integer,
+    Number,
+    Date,
+    /// 中国专利申请号（含校验位）
+    ApplicationNumber,
+}
+
+/// 校验报告
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidationReport {
+    pub import_mode: ImportMode,
+    pub files: Vec<FileValidation>,
+}
+
+impl ValidationReport {
+    pub fn failed_files(&self) -> impl Iterator<Item = &FileValidation> {
+        self.files.iter().filter(|f| !f.passed)
+    }
+}
+
+/// 单个文件的校验结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileValidation {
+    pub file: String,
+    pub passed: bool,
+    pub rows_checked: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// 被跳过的工作表
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SkippedSheet {
+    pub sheet: String,
+    pub reason: String,
+}
+
+/// 单个文件的转换结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConvertedFile {
+    pub source: String,
+    pub target: String,
+    /// 生成的全部文件（CSV/TSV 输出时每个工作表一个文件），第一个即 `target`；试运行时为预计的输出路径
+    pub outputs: Vec<String>,
+    /// 已转换的工作表
+    pub sheets: Vec<ConvertedSheet>,
+    pub skipped_sheets: Vec<SkippedSheet>,
+    /// 写入的数据行数
+    pub rows_written: usize,
+    /// 被本地导入过滤丢弃的行数
+    pub filtered_rows: usize,
+    /// 脚本返回跳过信号的行数
+    pub script_skipped_rows: usize,
+    pub row_errors: Vec<RowError>,
+    /// 与其他位置重复且未被保留的行
+    pub duplicate_rows: Vec<DuplicateRow>,
+    /// 写入合并文件的行，此时 `outputs` 中的合并文件可能与其他源文件共用
+    pub merged_rows: Vec<MergedRange>,
+    /// 各工作表中没有映射规则、按原名输出的源列（去重）
+    pub unmapped_headers: Vec<String>,
+    /// 在该文件中没有匹配到任何源列的列映射
+    pub unmatched_mappings: Vec<ColumnMapping>,
+    /// 至少一个工作表缺少的必需列
+    pub missing_columns: Vec<String>,
+    /// 文件被整体跳过、没有生成输出的原因，如所有工作表均被跳过
+    pub skipped: Option<String>,
+}
+
+/// 单个工作表的转换结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConvertedSheet {
+    pub name: String,
+    /// 输出的表头（映射后，包含计算列）
+    pub headers: Vec<String>,
+    /// 写入的数据行数
+    pub rows: usize,
+    /// 应用了映射规则的源列
+    pub mapped_headers: Vec<ColumnMapping>,
+    /// 没有映射规则、按原名输出的源列
+    pub unmapped_headers: Vec<String>,
+    /// 输出表头中缺少的必需列
+    pub missing_columns: Vec<String>,
+}
+
+/// 数据行的处理结果
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RowStatus {
+    /// 写入输出
+    Kept,
+    /// 脚本返回跳过信号
+    ScriptSkipped,
+    /// 被本地导入过滤丢弃
+    Filtered,
+    /// 脚本执行失败并按 on_error 跳过
+    Failed,
+}
+
+/// 单元格类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CellType {
+    Empty,
+    String,
+    Int,
+    Float,
+    Bool,
+    DateTime,
+    Duration,
+    Error,
+}
+
+/// 预览的单元格
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PreviewCell {
+    pub value: String,
+    #[serde(rename = "type")]
+    pub cell_type: CellType,
+}
+
+/// 预览的源数据行
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PreviewRow {
+    /// 源文件中的行号，从 1 开始
+    pub row: usize,
+    pub cells: Vec<PreviewCell>,
+}
+
+/// 工作簿预览
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkbookPreview {
+    pub path: String,
+    /// 全部工作表名称
+    pub sheets: Vec<String>,
+    /// 预览的工作表
+    pub sheet: String,
+    /// 表头所在行，从 1 开始
+    pub header_row: Option<usize>,
+    pub headers: Vec<String>,
+    /// 前若干行数据
+    pub rows: Vec<PreviewRow>,
+    /// 工作表的数据行数（不含表头）
+    pub total_rows: usize,
+}
+
+/// 按当前配置转换后的预览行
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MappedRow {
+    /// 源文件中的行号，从 1 开始
+    pub row: usize,
+    pub values: Vec<String>,
+    pub status: RowStatus,
+    /// 脚本执行失败的原因
+    pub error: Option<String>,
+}
+
+/// 列映射和转换预览
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MappingPreview {
+    pub path: String,
+    pub sheet: String,
+    /// 输出的表头（映射后，包含计算列）
+    pub headers: Vec<String>,
+    /// 应用了映射规则的源列
+    pub mapped_headers: Vec<ColumnMapping>,
+    /// 没有映射规则、按原名输出的源列
+    pub unmapped_headers: Vec<String>,
+    pub rows: Vec<MappedRow>,
+    /// 按工作表选择规则该工作表会被跳过时的原因
+    pub skipped: Option<String>,
+}
+
+/// 合并文件中来自同一工作表的一段连续行
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MergedRange {
+    pub sheet: String,
+    /// 源文件中的起始行号
+    pub source_row: usize,
+    /// 合并文件路径
+    pub output: String,
+    /// 合并文件中的起始行号
+    pub output_row: usize,
+    pub rows: usize,
+}
+
+/// 源文件中出现的表头及其出现次数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HeaderFrequency {
+    pub header: String,
+    /// 包含该表头的工作表数
+    pub sheets: usize,
+    /// 包含该表头的文件数
+    pub files: usize,
+}
+
+/// 列映射建议的匹配方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// 与目标列名完全相同
+    Exact,
+    /// 与目标列定义中的别名相同
+    Alias,
+    /// 忽略大小写、全半角、空白和标点后相同
+    Normalized,
+    /// 拼音相同，或拼音首字母与目标列名相同
+    Pinyin,
+    /// 相似但不相同
+    Fuzzy,
+}
+
+/// 一条列映射建议
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MappingSuggestion {
+    /// 源文件中的表头
+    pub original: String,
+    /// 建议映射到的目标列
+    pub mapped: String,
+    /// 相似度，0 到 1
+    pub score: f64,
+    #[serde(rename = "match")]
+    pub match_kind: MatchKind,
+    /// 包含该表头的工作表数
+    pub frequency: usize,
+}
+
+/// 列映射建议结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MappingSuggestions {
+    /// 所有不同的表头，按出现次数从多到少排列
+    pub headers: Vec<HeaderFrequency>,
+    /// 按相似度从高到低排列的建议，每个表头最多保留几个候选
+    pub suggestions: Vec<MappingSuggestion>,
+    /// 没有任何候选的表头
+    pub unmatched: Vec<String>,
+    /// 无法读取的文件
+    pub failed_files: Vec<RejectedFile>,
+    /// 无法读取、已跳过的工作表，所在文件的其他工作表照常统计
+    pub failed_sheets: Vec<FailedSheet>,
+}
+
+/// 无法读取的工作表
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FailedSheet {
+    pub file: String,
+    pub sheet: String,
+    pub reason: String,
+}
+
+/// 命令行工具一次运行的报告
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunReport {
+    /// 工具版本
+    pub version: String,
+    pub started_at: chrono::DateTime<chrono::Local>,
+    pub finished_at: chrono::DateTime<chrono::Local>,
+    /// 总耗时（毫秒）
+    pub duration_ms: u64,
+    pub server: String,
+    pub username: String,
+    pub input: String,
+    pub output: String,
+    pub import_mode: ImportMode,
+    pub dry_run: bool,
+    /// 扫描到的文件数
+    pub files_found: usize,
+    /// 转换耗时（毫秒），未转换时为 None
+    pub conversion_ms: Option<u64>,
+    /// 每个源文件的转换结果
+    pub conversions: Vec<ConvertedFile>,
+    /// 每个待上传文件的上传结果
+    pub uploads: Vec<UploadReport>,
+    pub summary: RunSummary,
+    /// 导致运行中止的错误
+    pub error: Option<String>,
+    /// 进程退出码
+    pub exit_code: u8,
+}
+
+/// 单个文件的上传结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UploadReport {
+    pub file: String,
+    /// 生成该文件的源文件，直接上传时为文件本身
+    pub sources: Vec<String>,
+    pub status: UploadStatus,
+    #[serde(rename = "modifiedCount")]
+    pub modified_count: Option<i64>,
+    #[serde(rename = "upsertedCount")]
+    pub upserted_count: Option<i64>,
+    #[serde(rename = "excelCount")]
+    pub excel_count: Option<i64>,
+    /// 上传请求的次数，包含重试
+    pub attempts: u32,
+    /// 上传耗时（毫秒），包含重试的等待时间
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+/// 上传状态
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadStatus {
+    Succeeded,
+    Failed,
+    /// 校验未通过，未上传
+    Rejected,
+    /// 指定了 --fail-fast，在之前的文件失败后停止，未上传
+    NotUploaded,
+}
+
+/// 运行汇总
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RunSummary {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub not_uploaded: usize,
+    #[serde(rename = "modifiedCount")]
+    pub modified_count: i64,
+    #[serde(rename = "upsertedCount")]
+    pub upserted_count: i64,
+    #[serde(rename = "excelCount")]
+    pub excel_count: i64,
+}
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use crate::cli::ImportMode;
+use crate::commands::types::ValidationSchema;
+use crate::excel;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UploadResult {
@@ -18,26 +21,92 @@ pub struct UploadData {
     pub excel_count: i32,
 }
 
-/// 上传文件到服务器
+/// 导入接口地址
+pub fn import_url(server_url: &str, import_mode: &str) -> String {
+    format!(
+        "{}/admin/patent/import?importMode={}",
+        server_url.trim_end_matches('/'),
+        import_mode
+    )
+}
+
+/// 按校验规则检查待上传的文件，与 patent-cli upload 的 --force-upload 行为一致
+async fn check_file(
+    file_path: &str,
+    schema: &ValidationSchema,
+    import_mode: &str,
+    force_upload: bool,
+) -> Result<(), String> {
+    let mode: ImportMode = serde_json::from_value(serde_json::Value::String(import_mode.to_string()))
+        .map_err(|_| format!("无效的导入模式: {}", import_mode))?;
+    let report = excel::validate_files(&[file_path.to_string()], schema, mode)
+        .await
+        .map_err(|e| {
+            let error_msg = format!("校验文件失败: {}", e);
+            tracing::error!("{}", error_msg);
+            error_msg
+        })?;
+
+    let Some(failed) = report.failed_files().next() else {
+        return Ok(());
+    };
+    if force_upload {
+        tracing::warn!("校验未通过 ({} 个问题)，已指定强制上传: {}", failed.issues.len(), file_path);
+        return Ok(());
+    }
+
+    let details: Vec<String> = failed
+        .issues
+        .iter()
+        .take(3)
+        .map(|issue| format!("{} 第 {} 行 {}: {}", issue.sheet, issue.row, issue.column, issue.message))
+        .collect();
+    let error_msg = format!("校验未通过 ({} 个问题): {}", failed.issues.len(), details.join("; "));
+    tracing::error!("{}: {}", error_msg, file_path);
+    Err(error_msg)
+}
+
+/// 上传文件到服务器，试运行时只检查文件并返回上传地址，不发送请求
+///
+/// 提供 `schema` 时先按校验规则检查文件，未通过的文件不上传，除非指定 `force_upload`。
 #[tauri::command]
 pub async fn upload_file(
     file_path: String,
     server_url: String,
     token: String,
     import_mode: String,
+    dry_run: Option<bool>,
+    schema: Option<ValidationSchema>,
+    force_upload: Option<bool>,
 ) -> Result<UploadResult, String> {
     tracing::info!("开始上传文件: {}, 导入模式: {}", file_path, import_mode);
 
     // 检查文件是否存在
     let path = Path::new(&file_path);
-    if !path.exists() {
+    if !Path::new(excel::archive::physical_path(&file_path)).exists() {
         let error_msg = format!("文件不存在: {}", file_path);
         tracing::error!("{}", error_msg);
         return Err(error_msg);
     }
 
-    // 读取文件内容
-    let file_content = tokio::fs::read(&file_path)
+    if let Some(schema) = &schema {
+        check_file(&file_path, schema, &import_mode, force_upload.unwrap_or(false)).await?;
+    }
+
+    // 构建上传 URL
+    let upload_url = import_url(&server_url, &import_mode);
+
+    if dry_run.unwrap_or(false) {
+        tracing::info!("试运行，跳过上传: {} -> {}", file_path, upload_url);
+        return Ok(UploadResult {
+            success: true,
+            data: None,
+            message: Some(format!("试运行，未上传: {}", upload_url)),
+        });
+    }
+
+    // 读取文件内容（压缩包内的文件直接从压缩包读取）
+    let file_content = excel::archive::read(&file_path)
         .await
         .map_err(|e| {
             let error_msg = format!("读取文件失败: {}", e);
@@ -66,12 +135,6 @@ pub async fn upload_file(
                 .map_err(|e| format!("设置 MIME 类型失败: {}", e))?,
         );
 
-    // 构建上传 URL
-    let upload_url = format!(
-        "{}/admin/patent/import?importMode={}",
-        server_url.trim_end_matches('/'),
-        import_mode
-    );
     tracing::info!("上传 URL: {}", upload_url);
 
     // 发送请求
@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+
+/// 计算校验位时各位数字的权重
+const CHECK_WEIGHTS: [u32; 12] = [2, 3, 4, 5, 6, 7, 8, 9, 2, 3, 4, 5];
+
+/// 专利类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatentType {
+    /// 发明
+    Invention,
+    /// 实用新型
+    UtilityModel,
+    /// 外观设计
+    Design,
+    /// 进入中国国家阶段的 PCT 发明申请
+    PctInvention,
+    /// 进入中国国家阶段的 PCT 实用新型申请
+    PctUtilityModel,
+}
+
+impl PatentType {
+    /// 申请号中的专利类型代码
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            b'1' => Some(PatentType::Invention),
+            b'2' => Some(PatentType::UtilityModel),
+            b'3' => Some(PatentType::Design),
+            b'8' => Some(PatentType::PctInvention),
+            b'9' => Some(PatentType::PctUtilityModel),
+            _ => None,
+        }
+    }
+
+    pub fn is_invention(self) -> bool {
+        matches!(self, PatentType::Invention | PatentType::PctInvention)
+    }
+
+    pub fn is_pct(self) -> bool {
+        matches!(self, PatentType::PctInvention | PatentType::PctUtilityModel)
+    }
+}
+
+/// 解析后的中国专利申请号
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApplicationNumber {
+    /// 规范化后的申请号，例如 "202310001234.5"
+    pub normalized: String,
+    /// 申请年份
+    pub year: i32,
+    pub patent_type: PatentType,
+    pub check_digit: char,
+}
+
+impl ApplicationNumber {
+    /// 解析申请号并校验校验位
+    ///
+    /// 支持 12 位（2003 年 10 月起）和 8 位（旧版）申请号，可带 "CN"/"ZL" 前缀，
+    /// 校验位可以用 "." 分隔，也可以直接跟在数字之后。
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (digits, check_digit) = split_number(value)?;
+        let check_digit = check_digit.ok_or_else(|| "缺少校验位".to_string())?;
+
+        // split_number 已保证全部为数字
+        let to_number = |s: &str| s.bytes().fold(0, |acc, b| acc * 10 + i32::from(b - b'0'));
+        let (year, type_code) = match digits.len() {
+            12 => (to_number(&digits[..4]), digits.as_bytes()[4]),
+            8 => {
+                let yy = to_number(&digits[..2]);
+                (if yy >= 85 { 1900 + yy } else { 2000 + yy }, digits.as_bytes()[2])
+            }
+            len => return Err(format!("申请号应为 12 位或 8 位数字，实际为 {} 位", len)),
+        };
+
+        if digits.len() == 12 && year < 2003 {
+            return Err(format!("12 位申请号的年份无效: {}", year));
+        }
+        if digits.len() == 8 && !(1985..=2003).contains(&year) {
+            return Err(format!("8 位申请号的年份无效: {}", year));
+        }
+
+        let patent_type = PatentType::from_code(type_code)
+            .ok_or_else(|| format!("未知的专利类型代码: {}", type_code as char))?;
+
+        let expected = compute_check_digit(&digits);
+        if check_digit != expected {
+            return Err(format!("校验位错误: 应为 {}，实际为 {}", expected, check_digit));
+        }
+
+        Ok(Self {
+            normalized: format!("{}.{}", digits, check_digit),
+            year,
+            patent_type,
+            check_digit,
+        })
+    }
+}
+
+/// 计算申请号的校验位，余数为 10 时校验位为 X
+pub fn compute_check_digit(digits: &str) -> char {
+    let sum: u32 = digits
+        .bytes()
+        .zip(CHECK_WEIGHTS.iter())
+        .map(|(digit, weight)| u32::from(digit - b'0') * weight)
+        .sum();
+    match sum % 11 {
+        10 => 'X',
+        n => char::from_digit(n, 10).unwrap_or('0'),
+    }
+}
+
+/// 从申请号推断专利类型，不要求校验位正确
+pub fn patent_type(value: &str) -> Option<PatentType> {
+    let (digits, _) = split_number(value).ok()?;
+    match digits.len() {
+        12 => PatentType::from_code(digits.as_bytes()[4]),
+        8 => PatentType::from_code(digits.as_bytes()[2]),
+        _ => None,
+    }
+}
+
+/// 拆分出申请号的数字部分和校验位
+fn split_number(value: &str) -> Result<(String, Option<char>), String> {
+    let compact: String = value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase();
+    if compact.is_empty() {
+        return Err("申请号为空".to_string());
+    }
+
+    let body = compact
+        .strip_prefix("CN")
+        .or_else(|| compact.strip_prefix("ZL"))
+        .unwrap_or(&compact);
+
+    let (digits, check_digit) = match body.split_once('.') {
+        Some((digits, check)) => {
+            let mut chars = check.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => (digits.to_string(), Some(c)),
+                _ => return Err(format!("校验位格式错误: {}", check)),
+            }
+        }
+        // 校验位直接跟在数字之后，例如 2023100012345
+        None if matches!(body.len(), 9 | 13) => {
+            let (digits, check) = body.split_at(body.len() - 1);
+            (digits.to_string(), check.chars().next())
+        }
+        None => (body.to_string(), None),
+    };
+
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("申请号包含非数字字符: {}", value.trim()));
+    }
+    if let Some(c) = check_digit {
+        if !(c.is_ascii_digit() || c == 'X') {
+            return Err(format!("校验位只能是数字或 X: {}", c));
+        }
+    }
+
+    Ok((digits, check_digit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_twelve_digit_numbers() {
+        let number = ApplicationNumber::parse("201310123456.2").unwrap();
+        assert_eq!(number.normalized, "201310123456.2");
+        assert_eq!(number.year, 2013);
+        assert_eq!(number.patent_type, PatentType::Invention);
+        assert_eq!(number.check_digit, '2');
+
+        // 前缀、空格和不带 "." 的校验位
+        assert_eq!(ApplicationNumber::parse("CN 2013 1012 3456.2").unwrap().normalized, "201310123456.2");
+        assert_eq!(ApplicationNumber::parse("ZL2013101234562").unwrap().normalized, "201310123456.2");
+    }
+
+    #[test]
+    fn parses_eight_digit_numbers() {
+        let number = ApplicationNumber::parse("98100001.0").unwrap();
+        assert_eq!(number.year, 1998);
+        assert_eq!(number.patent_type, PatentType::Invention);
+
+        let number = ApplicationNumber::parse("88200012.8").unwrap();
+        assert_eq!(number.year, 1988);
+        assert_eq!(number.patent_type, PatentType::UtilityModel);
+
+        assert_eq!(ApplicationNumber::parse("02100001.8").unwrap().year, 2002);
+    }
+
+    #[test]
+    fn accepts_x_check_digit() {
+        assert_eq!(compute_check_digit("201310123451"), 'X');
+        assert_eq!(ApplicationNumber::parse("201310123451.X").unwrap().check_digit, 'X');
+        assert_eq!(ApplicationNumber::parse("201310123451.x").unwrap().normalized, "201310123451.X");
+        assert!(ApplicationNumber::parse("201310123451.1").is_err());
+    }
+
+    #[test]
+    fn derives_patent_type() {
+        assert_eq!(ApplicationNumber::parse("201330012345.5").unwrap().patent_type, PatentType::Design);
+        let pct = ApplicationNumber::parse("201480012345.7").unwrap();
+        assert_eq!(pct.patent_type, PatentType::PctInvention);
+        assert!(pct.patent_type.is_pct());
+        assert!(pct.patent_type.is_invention());
+
+        // 推断专利类型不要求校验位
+        assert_eq!(patent_type("202320001234"), Some(PatentType::UtilityModel));
+        assert_eq!(patent_type("CN98100001"), Some(PatentType::Invention));
+        assert_eq!(patent_type("201340012345"), None);
+        assert_eq!(patent_type(""), None);
+    }
+
+    #[test]
+    fn rejects_invalid_numbers() {
+        assert_eq!(ApplicationNumber::parse("201310123456").unwrap_err(), "缺少校验位");
+        assert!(ApplicationNumber::parse("201310123456.3").unwrap_err().contains("校验位错误"));
+        assert!(ApplicationNumber::parse("200210012345.0").unwrap_err().contains("年份无效"));
+        assert!(ApplicationNumber::parse("80100001.0").unwrap_err().contains("年份无效"));
+        assert!(ApplicationNumber::parse("20131012345.6").unwrap_err().contains("11 位"));
+        assert!(ApplicationNumber::parse("2013101234A6.2").is_err());
+        assert!(ApplicationNumber::parse("").is_err());
+    }
+}
@@ -0,0 +1,216 @@
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+use crate::commands::types::{FileFormat, RejectedFile};
+use super::format::{self, Detection};
+use super::scan::PathFilter;
+
+/// 虚拟路径中压缩包与其内部路径的分隔符，例如 `导出.zip!/2024/专利.xlsx`
+pub const SEPARATOR: &str = "!/";
+
+/// 嵌套压缩包读入内存的大小上限（解压后）
+const MAX_NESTED_ARCHIVE_SIZE: u64 = 512 * 1024 * 1024;
+
+/// 压缩包中的工作簿
+pub(crate) struct ArchiveEntry {
+    pub path: String,
+    pub size: u64,
+    pub format: FileFormat,
+}
+
+/// 是否为 ZIP 压缩包（按扩展名，不区分大小写）
+pub fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.eq_ignore_ascii_case("zip"))
+        .unwrap_or(false)
+}
+
+/// 拆分虚拟路径，返回磁盘上的压缩包路径和其内部路径
+///
+/// 只有分隔符前是扩展名为 `.zip` 的磁盘文件时才视为虚拟路径，
+/// 目录名中恰好含有 `!/` 的普通文件仍按原路径读取。
+pub fn virtual_parts(path: &str) -> Option<(&str, &str)> {
+    archive_splits(path).find(|(archive, _)| Path::new(archive).is_file())
+}
+
+/// 虚拟路径所在的磁盘文件，普通路径原样返回
+pub fn physical_path(path: &str) -> &str {
+    virtual_parts(path).map(|(archive, _)| archive).unwrap_or(path)
+}
+
+/// 读取输入文件的全部内容，支持压缩包内（含嵌套）的虚拟路径
+pub fn read_input(path: &str) -> io::Result<Vec<u8>> {
+    match virtual_parts(path) {
+        None => std::fs::read(path),
+        Some((archive, inner)) => {
+            let zip = ZipArchive::new(BufReader::new(File::open(archive)?)).map_err(zip_error)?;
+            read_entry(zip, inner)
+        }
+    }
+}
+
+/// 异步读取输入文件的全部内容
+pub async fn read(path: &str) -> io::Result<Vec<u8>> {
+    if virtual_parts(path).is_none() {
+        return tokio::fs::read(path).await;
+    }
+
+    let path = path.to_string();
+    tokio::task::spawn_blocking(move || read_input(&path))
+        .await
+        .map_err(io::Error::other)?
+}
+
+/// 列出压缩包中的工作簿，`nested` 为 true 时继续展开其中的压缩包
+pub(crate) fn list_workbooks(
+    archive_path: &Path,
+    filter: &PathFilter,
+    nested: bool,
+) -> io::Result<(Vec<ArchiveEntry>, Vec<RejectedFile>)> {
+    let mut zip = ZipArchive::new(BufReader::new(File::open(archive_path)?)).map_err(zip_error)?;
+    let mut entries = Vec::new();
+    let mut rejected = Vec::new();
+
+    list_from(
+        &mut zip,
+        &archive_path.to_string_lossy(),
+        filter,
+        nested,
+        &mut entries,
+        &mut rejected,
+    )?;
+
+    tracing::info!("压缩包 {} 中发现 {} 个 Excel 文件", archive_path.display(), entries.len());
+    Ok((entries, rejected))
+}
+
+/// 转换时压缩包内文件的相对输出路径：以压缩包名（不含扩展名）作为目录
+pub fn mirror_path(relative: &Path) -> PathBuf {
+    let text = relative.to_string_lossy();
+    let mut rest: &str = &text;
+    let mut path = PathBuf::new();
+    while let Some((archive, inner)) = archive_splits(rest).next() {
+        path.push(Path::new(archive).with_extension(""));
+        rest = inner;
+    }
+    path.push(rest);
+    path
+}
+
+/// 路径中可能的压缩包分隔位置：分隔符前的部分扩展名为 `.zip`
+fn archive_splits(path: &str) -> impl Iterator<Item = (&str, &str)> {
+    path.match_indices(SEPARATOR)
+        .map(move |(index, _)| (&path[..index], &path[index + SEPARATOR.len()..]))
+        .filter(|(archive, _)| is_archive(Path::new(archive)))
+}
+
+fn list_from<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    prefix: &str,
+    filter: &PathFilter,
+    nested: bool,
+    entries: &mut Vec<ArchiveEntry>,
+    rejected: &mut Vec<RejectedFile>,
+) -> io::Result<()> {
+    // 只读取中央目录和文件头，工作簿内容在转换或上传时再读取
+    for index in 0..zip.len() {
+        let mut file = zip.by_index(index).map_err(zip_error)?;
+        if file.is_dir() {
+            continue;
+        }
+        if file.enclosed_name().is_none() {
+            tracing::warn!("跳过压缩包 {} 中路径不安全的文件: {}", prefix, file.name());
+            continue;
+        }
+
+        let name = file.name().to_string();
+        let virtual_path = format!("{}{}{}", prefix, SEPARATOR, name);
+        if !filter.allow_archive_entry(prefix, &name) {
+            continue;
+        }
+
+        let entry_path = Path::new(&name);
+        if is_archive(entry_path) {
+            if !nested {
+                tracing::debug!("未启用嵌套压缩包，跳过: {}", virtual_path);
+                continue;
+            }
+            let inner = read_nested(&mut file)
+                .and_then(|bytes| ZipArchive::new(Cursor::new(bytes)).map_err(zip_error));
+            match inner {
+                Ok(mut inner) => list_from(&mut inner, &virtual_path, filter, nested, entries, rejected)?,
+                Err(e) => rejected.push(RejectedFile {
+                    path: virtual_path,
+                    reason: format!("无法读取压缩包: {}", e),
+                }),
+            }
+            continue;
+        }
+
+        if !format::is_candidate(entry_path) || !filter.allow_file(Path::new(&virtual_path)) {
+            continue;
+        }
+
+        let mut header = Vec::new();
+        (&mut file).take(format::HEADER_SIZE).read_to_end(&mut header)?;
+        match format::detect_header(entry_path, &header) {
+            Detection::Workbook(format) => {
+                tracing::debug!("发现压缩包中的 Excel 文件: {}", virtual_path);
+                entries.push(ArchiveEntry {
+                    path: virtual_path,
+                    size: file.size(),
+                    format,
+                });
+            }
+            Detection::Rejected(reason) => rejected.push(RejectedFile {
+                path: virtual_path,
+                reason,
+            }),
+            Detection::Other => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// 从压缩包中读取文件，内部路径中 `.zip` 之后的分隔符表示嵌套的压缩包
+fn read_entry<R: Read + Seek>(mut zip: ZipArchive<R>, inner: &str) -> io::Result<Vec<u8>> {
+    let nested = archive_splits(inner).find(|(name, _)| zip.file_names().any(|entry| entry == *name));
+    let Some((name, rest)) = nested else {
+        return read_all(&mut zip.by_name(inner).map_err(zip_error)?);
+    };
+
+    let bytes = read_nested(&mut zip.by_name(name).map_err(zip_error)?)?;
+    read_entry(ZipArchive::new(Cursor::new(bytes)).map_err(zip_error)?, rest)
+}
+
+/// 将嵌套的压缩包读入内存，超过大小上限时返回错误
+fn read_nested(file: &mut zip::read::ZipFile<'_>) -> io::Result<Vec<u8>> {
+    if file.size() > MAX_NESTED_ARCHIVE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("嵌套压缩包过大（{} MB，上限 {} MB）", file.size() / 1024 / 1024, MAX_NESTED_ARCHIVE_SIZE / 1024 / 1024),
+        ));
+    }
+    // 不信任中央目录中记录的大小，读取时同样限制
+    let mut bytes = Vec::new();
+    file.take(MAX_NESTED_ARCHIVE_SIZE + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > MAX_NESTED_ARCHIVE_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "嵌套压缩包过大"));
+    }
+    Ok(bytes)
+}
+
+fn read_all(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn zip_error(e: zip::result::ZipError) -> io::Error {
+    match e {
+        zip::result::ZipError::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
@@ -0,0 +1,194 @@
+use crate::commands::types::{ComputedColumn, ComputedValue};
+
+/// 模板片段
+enum Segment {
+    Text(String),
+    Column(String),
+}
+
+/// 编译后的列值来源
+enum Source {
+    Constant(String),
+    FileName,
+    SheetName,
+    Template(Vec<Segment>),
+}
+
+/// 计算列生成器
+pub(crate) struct ColumnComputer {
+    columns: Vec<(String, Source)>,
+}
+
+/// 针对某个工作表解析后的计算列
+pub(crate) struct ComputedPlan {
+    entries: Vec<(usize, PlannedValue)>,
+    width: usize,
+}
+
+enum PlannedValue {
+    Fixed(String),
+    Template(Vec<PlannedSegment>),
+}
+
+enum PlannedSegment {
+    Text(String),
+    Column(Option<usize>),
+}
+
+impl ColumnComputer {
+    pub fn new(columns: &[ComputedColumn]) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let columns = columns
+            .iter()
+            .map(|column| {
+                if column.name.is_empty() {
+                    return Err("计算列的列名不能为空".into());
+                }
+                let source = match &column.value {
+                    ComputedValue::Constant { value } => Source::Constant(value.clone()),
+                    ComputedValue::FileName => Source::FileName,
+                    ComputedValue::SheetName => Source::SheetName,
+                    ComputedValue::Template { template } => Source::Template(
+                        parse_template(template)
+                            .map_err(|e| format!("计算列 {} 的模板无效: {}", column.name, e))?,
+                    ),
+                };
+                tracing::debug!("计算列: {}", column.name);
+                Ok((column.name.clone(), source))
+            })
+            .collect::<Result<_, Box<dyn std::error::Error + Send + Sync>>>()?;
+
+        Ok(Self { columns })
+    }
+
+    /// 将计算列追加到表头，并解析出该工作表的取值方式
+    pub fn plan(&self, headers: &mut Vec<String>, file_name: &str, sheet_name: &str) -> ComputedPlan {
+        let mut entries = Vec::with_capacity(self.columns.len());
+
+        for (name, source) in &self.columns {
+            let index = match headers.iter().position(|h| h == name) {
+                Some(index) => index,
+                None => {
+                    headers.push(name.clone());
+                    headers.len() - 1
+                }
+            };
+
+            let value = match source {
+                Source::Constant(value) => PlannedValue::Fixed(value.clone()),
+                Source::FileName => PlannedValue::Fixed(file_name.to_string()),
+                Source::SheetName => PlannedValue::Fixed(sheet_name.to_string()),
+                Source::Template(segments) => PlannedValue::Template(
+                    segments
+                        .iter()
+                        .map(|segment| match segment {
+                            Segment::Text(text) => PlannedSegment::Text(text.clone()),
+                            Segment::Column(column) => {
+                                let position = headers.iter().position(|h| h == column);
+                                if position.is_none() {
+                                    tracing::warn!("工作表 {} 中不存在计算列 {} 引用的列: {}", sheet_name, name, column);
+                                }
+                                PlannedSegment::Column(position)
+                            }
+                        })
+                        .collect(),
+                ),
+            };
+            entries.push((index, value));
+        }
+
+        ComputedPlan {
+            entries,
+            width: headers.len(),
+        }
+    }
+}
+
+impl ComputedPlan {
+    /// 按顺序计算并写入计算列，后面的计算列可以引用前面的结果
+    pub fn apply(&self, row: &mut Vec<String>) {
+        if row.len() < self.width {
+            row.resize(self.width, String::new());
+        }
+
+        for (index, value) in &self.entries {
+            let value = match value {
+                PlannedValue::Fixed(value) => value.clone(),
+                PlannedValue::Template(segments) => segments
+                    .iter()
+                    .map(|segment| match segment {
+                        PlannedSegment::Text(text) => text.as_str(),
+                        PlannedSegment::Column(Some(column)) => row[*column].as_str(),
+                        PlannedSegment::Column(None) => "",
+                    })
+                    .collect(),
+            };
+            row[*index] = value;
+        }
+    }
+}
+
+/// 将模板中对列 `from` 的引用改为 `to`，没有引用或模板无效时返回 None
+pub(crate) fn rename_template_column(template: &str, from: &str, to: &str) -> Option<String> {
+    let segments = parse_template(template).ok()?;
+    if !segments.iter().any(|segment| matches!(segment, Segment::Column(column) if column == from)) {
+        return None;
+    }
+
+    let mut renamed = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => renamed.push_str(&text.replace('{', "{{").replace('}', "}}")),
+            Segment::Column(column) => {
+                renamed.push('{');
+                renamed.push_str(if column == from { to } else { &column });
+                renamed.push('}');
+            }
+        }
+    }
+    Some(renamed)
+}
+
+/// 解析模板，`{列名}` 为列引用，`{{`、`}}` 为字面的花括号
+fn parse_template(template: &str) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut column = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => column.push(c),
+                        None => return Err("缺少 '}'".to_string()),
+                    }
+                }
+                if column.trim().is_empty() {
+                    return Err("列名不能为空".to_string());
+                }
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Column(column.trim().to_string()));
+            }
+            '}' => return Err("多余的 '}'".to_string()),
+            c => text.push(c),
+        }
+    }
+
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+
+    Ok(segments)
+}
@@ -0,0 +1,217 @@
+use std::path::{Path, PathBuf};
+use crate::commands::types::{ConsolidateConfig, MergedRange, OutputFormat, SplitConfig, TextEncoding};
+use super::writer::{CollectedSheet, OutputWriter};
+
+/// 合并文件中的工作表名
+const MERGED_SHEET: &str = "Sheet1";
+
+/// 按表头将小文件的工作表分组合并
+pub(crate) struct Consolidator {
+    max_rows: usize,
+    /// 本次运行的合并文件所在目录
+    directory: PathBuf,
+    /// 本次运行的合并文件名前缀，包含开始时间，避免覆盖之前运行生成的合并文件
+    prefix: String,
+    format: OutputFormat,
+    encoding: TextEncoding,
+    next_index: usize,
+    groups: Vec<Group>,
+}
+
+/// 表头兼容的一组工作表
+struct Group {
+    /// 规范化并排序后的列名
+    key: Vec<String>,
+    output: MergedOutput,
+}
+
+/// 待写出的合并文件
+pub(crate) struct MergedOutput {
+    pub path: PathBuf,
+    format: OutputFormat,
+    encoding: TextEncoding,
+    headers: Vec<String>,
+    /// 源文件中的行号和按本组表头排列的值
+    rows: Vec<(usize, Vec<String>)>,
+    /// 写入的行来自哪个源文件（转换结果中的序号）
+    pub ranges: Vec<(usize, MergedRange)>,
+}
+
+impl Consolidator {
+    /// 合并文件输出到 `target_root` 下以 `config.name` 命名的目录，不与转换后的文件混在一起
+    pub fn new(config: &ConsolidateConfig, target_root: &Path, format: OutputFormat, encoding: TextEncoding) -> Self {
+        Self {
+            max_rows: config.max_rows.max(1),
+            directory: target_root.join(&config.name),
+            prefix: format!("{}-{}", config.name, chrono::Local::now().format("%Y%m%d-%H%M%S")),
+            format,
+            encoding,
+            next_index: 1,
+            groups: Vec::new(),
+        }
+    }
+
+    /// 加入一个源文件的工作表，返回已满、可以写出的合并文件
+    pub fn add(&mut self, file_index: usize, sheets: Vec<CollectedSheet>) -> Vec<MergedOutput> {
+        let mut full = Vec::new();
+
+        for sheet in sheets {
+            if sheet.rows.is_empty() || sheet.headers.is_empty() {
+                continue;
+            }
+            let key = header_key(&sheet.headers);
+            let position = match self.groups.iter().position(|group| group.key == key) {
+                Some(position) if self.groups[position].output.rows.len() + sheet.rows.len() > self.max_rows => {
+                    let group = self.groups.remove(position);
+                    full.push(group.output);
+                    None
+                }
+                position => position,
+            };
+            let position = position.unwrap_or_else(|| {
+                let output = self.new_output(sheet.headers.clone());
+                self.groups.push(Group { key, output });
+                self.groups.len() - 1
+            });
+            self.groups[position].output.append(file_index, sheet);
+        }
+
+        full
+    }
+
+    /// 返回尚未写出的合并文件
+    pub fn finish(self) -> Vec<MergedOutput> {
+        let mut outputs: Vec<MergedOutput> = self.groups.into_iter().map(|group| group.output).collect();
+        outputs.sort_by(|a, b| a.path.cmp(&b.path));
+        outputs
+    }
+
+    fn new_output(&mut self, headers: Vec<String>) -> MergedOutput {
+        // 同一秒内开始的多次运行（如监控模式的相邻批次）跳过已存在的文件
+        let path = loop {
+            let file_name = format!("{}-{:03}.{}", self.prefix, self.next_index, self.format.extension());
+            self.next_index += 1;
+            let path = self.directory.join(file_name);
+            if !path.exists() {
+                break path;
+            }
+        };
+        MergedOutput {
+            path,
+            format: self.format,
+            encoding: self.encoding,
+            headers,
+            rows: Vec::new(),
+            ranges: Vec::new(),
+        }
+    }
+}
+
+impl MergedOutput {
+    /// 按本组表头的顺序追加工作表的数据行，并记录来源
+    fn append(&mut self, file_index: usize, sheet: CollectedSheet) {
+        let columns = column_order(&self.headers, &sheet.headers);
+        let output = self.path.to_string_lossy().to_string();
+        let mut current: Option<MergedRange> = None;
+
+        for (source_row, values) in sheet.rows {
+            // 合并文件中的行号，第 1 行为表头
+            let output_row = self.rows.len() + 2;
+            self.rows.push((
+                source_row,
+                columns
+                    .iter()
+                    .map(|column| column.and_then(|index| values.get(index)).cloned().unwrap_or_default())
+                    .collect(),
+            ));
+
+            match &mut current {
+                Some(range) if range.source_row + range.rows == source_row => range.rows += 1,
+                _ => {
+                    if let Some(range) = current.take() {
+                        self.ranges.push((file_index, range));
+                    }
+                    current = Some(MergedRange {
+                        sheet: sheet.name.clone(),
+                        source_row,
+                        output: output.clone(),
+                        output_row,
+                        rows: 1,
+                    });
+                }
+            }
+        }
+
+        if let Some(range) = current {
+            self.ranges.push((file_index, range));
+        }
+    }
+
+    /// 写出合并文件
+    pub fn write(&self) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+        tracing::info!("写出合并文件: {} ({} 行)", self.path.display(), self.rows.len());
+        let mut output = OutputWriter::create(&self.path, self.format, self.encoding, None)?;
+        let mut worksheet = output.add_sheet(MERGED_SHEET, &self.headers)?;
+        for (source_row, values) in &self.rows {
+            worksheet.write_row(*source_row, values)?;
+        }
+        worksheet.finish()?;
+        output.close()
+    }
+}
+
+/// 将收集到内存中的工作表写为普通的输出文件；试运行时只返回会生成的文件，不写出文件
+pub(crate) fn write_collected(
+    target: &Path,
+    format: OutputFormat,
+    encoding: TextEncoding,
+    split: Option<&SplitConfig>,
+    sheets: &[CollectedSheet],
+    dry_run: bool,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut output = if dry_run {
+        OutputWriter::plan(target, format, split)?
+    } else {
+        OutputWriter::create(target, format, encoding, split)?
+    };
+    for sheet in sheets {
+        if output.is_full() {
+            output.next_part()?;
+        }
+        let mut worksheet = output.add_sheet(&sheet.name, &sheet.headers)?;
+        for (source_row, values) in &sheet.rows {
+            if worksheet.is_full() {
+                worksheet.finish()?;
+                output.next_part()?;
+                worksheet = output.add_sheet(&sheet.name, &sheet.headers)?;
+            }
+            worksheet.write_row(*source_row, values)?;
+        }
+        worksheet.finish()?;
+    }
+    output.close()
+}
+
+/// 比较表头时忽略列的顺序、大小写和首尾空白
+fn header_key(headers: &[String]) -> Vec<String> {
+    let mut key: Vec<String> = headers.iter().map(|h| h.trim().to_lowercase()).collect();
+    key.sort();
+    key
+}
+
+/// 目标表头每一列在源表头中的位置，同名列按出现顺序对应
+fn column_order(target: &[String], source: &[String]) -> Vec<Option<usize>> {
+    let mut used = vec![false; source.len()];
+    target
+        .iter()
+        .map(|header| {
+            let header = header.trim().to_lowercase();
+            let index = source
+                .iter()
+                .enumerate()
+                .position(|(index, h)| !used[index] && h.trim().to_lowercase() == header)?;
+            used[index] = true;
+            Some(index)
+        })
+        .collect()
+}
@@ -0,0 +1,282 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+use crate::commands::types::{
+    DuplicateConfig, DuplicateGroup, DuplicateReport, DuplicateRow, DuplicateWinner, FailedSheet, RowLocation,
+    RowStatus,
+};
+use super::application_number::ApplicationNumber;
+use super::{cell_to_string, Converter};
+
+/// 按工作表整理的待移除行号
+pub(crate) type RemovedRows = HashMap<String, HashSet<usize>>;
+
+/// 文件中带识别值的一行
+struct KeyedRow {
+    key: String,
+    location: RowLocation,
+    filled: usize,
+}
+
+/// 索引中的一行
+struct IndexedRow {
+    location: RowLocation,
+    /// 非空单元格数
+    filled: usize,
+    modified: Option<SystemTime>,
+}
+
+/// 建立所有文件的专利索引，找出重复的专利
+///
+/// 只索引经过列值转换、计算列、脚本和导入过滤后仍会写入输出的行，
+/// 保留的行因此不会在转换时被过滤掉。无法读取的工作表记入报告后跳过。
+pub(crate) async fn build_report(
+    files: &[String],
+    converter: &Arc<Converter>,
+    config: &DuplicateConfig,
+) -> Result<DuplicateReport, Box<dyn std::error::Error + Send + Sync>> {
+    tracing::info!("开始检测重复专利，文件数: {}, 识别列: {}", files.len(), config.key_columns.join(", "));
+
+    let key_columns = Arc::new(config.key_columns.clone());
+    let mut order: Vec<String> = Vec::new();
+    let mut index: HashMap<String, Vec<IndexedRow>> = HashMap::new();
+    let mut failed_sheets = Vec::new();
+
+    for file_path in files {
+        let modified = tokio::fs::metadata(super::archive::physical_path(file_path))
+            .await
+            .and_then(|m| m.modified())
+            .ok();
+
+        let file = file_path.clone();
+        let file_converter = Arc::clone(converter);
+        let file_key_columns = Arc::clone(&key_columns);
+        let (rows, failed) = tokio::task::spawn_blocking(move || {
+            index_file(&file, &file_converter, &file_key_columns)
+        }).await??;
+        failed_sheets.extend(failed);
+
+        for row in rows {
+            let entries = index.entry(row.key.clone()).or_insert_with(|| {
+                order.push(row.key);
+                Vec::new()
+            });
+            entries.push(IndexedRow {
+                location: row.location,
+                filled: row.filled,
+                modified,
+            });
+        }
+    }
+
+    let mut report = DuplicateReport {
+        failed_sheets,
+        ..DuplicateReport::default()
+    };
+    for key in order {
+        let rows = &index[&key];
+        if rows.len() < 2 {
+            continue;
+        }
+
+        let kept = pick_winner(rows, config.keep);
+        tracing::warn!(
+            "重复专利 {}: {} 处，保留 {} [{}] 第 {} 行",
+            key,
+            rows.len(),
+            rows[kept].location.file,
+            rows[kept].location.sheet,
+            rows[kept].location.row
+        );
+
+        report.duplicate_rows += rows.len() - 1;
+        report.groups.push(DuplicateGroup {
+            key,
+            locations: rows.iter().map(|r| r.location.clone()).collect(),
+            kept,
+        });
+    }
+
+    tracing::info!("重复专利检测完成，{} 个专利重复，涉及 {} 行", report.groups.len(), report.duplicate_rows);
+
+    Ok(report)
+}
+
+/// 按文件整理未被保留的重复行
+pub(crate) fn rows_by_file(report: &DuplicateReport, remove: bool) -> HashMap<String, Vec<DuplicateRow>> {
+    let mut result: HashMap<String, Vec<DuplicateRow>> = HashMap::new();
+
+    for group in &report.groups {
+        let kept = &group.locations[group.kept];
+        for (index, location) in group.locations.iter().enumerate() {
+            if index == group.kept {
+                continue;
+            }
+            result.entry(location.file.clone()).or_default().push(DuplicateRow {
+                sheet: location.sheet.clone(),
+                row: location.row,
+                key: group.key.clone(),
+                removed: remove,
+                kept: kept.clone(),
+            });
+        }
+    }
+
+    result
+}
+
+/// 整理出需要移除的行
+pub(crate) fn removed_rows(rows: &[DuplicateRow]) -> RemovedRows {
+    let mut removed = RemovedRows::new();
+    for row in rows.iter().filter(|r| r.removed) {
+        removed.entry(row.sheet.clone()).or_default().insert(row.row);
+    }
+    removed
+}
+
+/// 读取单个文件中每一行的识别值，同时返回无法读取的工作表
+fn index_file(
+    file_path: &str,
+    converter: &Converter,
+    key_columns: &[String],
+) -> Result<(Vec<KeyedRow>, Vec<FailedSheet>), Box<dyn std::error::Error + Send + Sync>> {
+    let mut workbook = super::format::open_workbook(file_path)?;
+    let mut rows = Vec::new();
+    let mut failed_sheets = Vec::new();
+    let file_name = Path::new(file_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    for (sheet_index, sheet_name) in workbook.sheet_names().to_owned().iter().enumerate() {
+        if converter.sheet_filter.check_name(sheet_index, sheet_name).is_some() {
+            continue;
+        }
+
+        let range = match workbook.worksheet_range(sheet_name) {
+            Ok(range) => range,
+            Err(e) => {
+                tracing::warn!("读取工作表失败，跳过重复检测 {} [{}]: {}", file_path, sheet_name, e);
+                failed_sheets.push(FailedSheet {
+                    file: file_path.to_string(),
+                    sheet: sheet_name.clone(),
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let header_row = range.start().map(|(row, _)| row as usize).unwrap_or(0);
+        let mut sheet_rows = range.rows();
+
+        let headers: Vec<String> = match sheet_rows.next() {
+            Some(header) => header
+                .iter()
+                .map(|cell| converter.map_header(&cell_to_string(cell)).to_string())
+                .collect(),
+            None => continue,
+        };
+        if converter.sheet_filter.check_headers(&headers).is_some() {
+            continue;
+        }
+
+        // 按转换时的规则处理每一行，识别值和非空单元格数取转换后的值
+        let plan = converter.plan_sheet(headers, &file_name, sheet_name);
+        let key_indexes: Vec<usize> = key_columns
+            .iter()
+            .filter_map(|column| plan.headers.iter().position(|h| h == column))
+            .collect();
+        if key_indexes.is_empty() {
+            tracing::debug!("工作表 {} 中没有识别列，跳过重复检测", sheet_name);
+            continue;
+        }
+
+        for (row_index, row) in sheet_rows.enumerate() {
+            let source_row = header_row + row_index + 2;
+            // 被导入过滤或脚本丢弃的行不会写入输出，不参与重复检测
+            let values = match converter.convert_row(&plan, row, sheet_name, source_row) {
+                Ok(result) if result.status == RowStatus::Kept => result.values,
+                _ => continue,
+            };
+            let key = key_indexes
+                .iter()
+                .filter_map(|&index| values.get(index))
+                .find(|value| !value.trim().is_empty())
+                .map(|value| normalize_key(value));
+
+            if let Some(key) = key {
+                let filled = values.iter().filter(|v| !v.trim().is_empty()).count();
+                rows.push(KeyedRow {
+                    key,
+                    location: RowLocation {
+                        file: file_path.to_string(),
+                        sheet: sheet_name.clone(),
+                        row: source_row,
+                    },
+                    filled,
+                });
+            }
+        }
+    }
+
+    Ok((rows, failed_sheets))
+}
+
+/// 按保留规则选出保留的行，返回下标
+fn pick_winner(rows: &[IndexedRow], rule: DuplicateWinner) -> usize {
+    // 相同条件下取后出现的行
+    let by_modified = |a: &IndexedRow, b: &IndexedRow| a.modified.cmp(&b.modified);
+    let best = match rule {
+        DuplicateWinner::First => return 0,
+        DuplicateWinner::LatestFile => rows.iter().enumerate().max_by(|(_, a), (_, b)| by_modified(a, b)),
+        DuplicateWinner::MostComplete => rows.iter().enumerate().max_by(|(_, a), (_, b)| {
+            match a.filled.cmp(&b.filled) {
+                Ordering::Equal => by_modified(a, b),
+                ordering => ordering,
+            }
+        }),
+    };
+    best.map(|(index, _)| index).unwrap_or(0)
+}
+
+/// 规范化识别值，合法的申请号统一为 "数字.校验位" 的形式
+fn normalize_key(value: &str) -> String {
+    match ApplicationNumber::parse(value) {
+        Ok(number) => number.normalized,
+        Err(_) => value
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_uppercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::types::ConvertConfig;
+    use crate::excel::testing::{temp_path, write_workbook};
+
+    #[test]
+    fn unreadable_sheet_is_recorded_and_skipped() {
+        let path = temp_path("xlsx");
+        write_workbook(
+            &path,
+            &[("有效", &[&["申请号"], &["CN201310123403.0"], &["CN201310123403.0"]])],
+            &["损坏"],
+        );
+        let converter = Converter::new(&ConvertConfig::default()).unwrap();
+        let file = path.to_string_lossy().to_string();
+
+        let result = index_file(&file, &converter, &["申请号".to_string()]);
+        std::fs::remove_file(&path).unwrap();
+        let (rows, failed) = result.unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|row| row.location.sheet == "有效"));
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].file, file);
+        assert_eq!(failed[0].sheet, "损坏");
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+use crate::cli::ImportMode;
+use crate::commands::types::ImportFilter;
+use super::application_number;
+
+/// 本地导入过滤器
+pub(crate) struct RowFilter {
+    mode: ImportMode,
+    application_number_column: String,
+    type_column: Option<String>,
+    invention_values: HashSet<String>,
+    legal_status_column: String,
+    valid_values: HashSet<String>,
+}
+
+/// 针对某个工作表解析后的过滤规则
+pub(crate) struct FilterPlan<'a> {
+    filter: &'a RowFilter,
+    number_index: Option<usize>,
+    type_index: Option<usize>,
+    status_index: Option<usize>,
+}
+
+impl RowFilter {
+    pub fn new(config: &ImportFilter) -> Self {
+        let normalize = |values: &[String]| values.iter().map(|v| v.trim().to_lowercase()).collect();
+
+        tracing::info!("启用本地导入过滤，导入模式: {}", config.mode);
+
+        Self {
+            mode: config.mode,
+            application_number_column: config.application_number_column.clone(),
+            type_column: config.type_column.clone(),
+            invention_values: normalize(&config.invention_values),
+            legal_status_column: config.legal_status_column.clone(),
+            valid_values: normalize(&config.valid_values),
+        }
+    }
+
+    /// 按表头定位过滤所需的列，导入模式为 all 时返回 None
+    pub fn plan(&self, headers: &[String], sheet_name: &str) -> Option<FilterPlan<'_>> {
+        if self.mode == ImportMode::All {
+            return None;
+        }
+
+        let position = |column: &str| headers.iter().position(|h| h == column);
+        let plan = FilterPlan {
+            filter: self,
+            number_index: position(&self.application_number_column),
+            type_index: self.type_column.as_deref().and_then(position),
+            status_index: position(&self.legal_status_column),
+        };
+
+        if plan.type_index.is_none() && plan.number_index.is_none() {
+            tracing::warn!("工作表 {} 缺少专利类型列和申请号列，无法按专利类型过滤", sheet_name);
+        }
+        if self.mode == ImportMode::ValidInventionOnly && plan.status_index.is_none() {
+            tracing::warn!("工作表 {} 缺少法律状态列 {}，无法按有效性过滤", sheet_name, self.legal_status_column);
+        }
+
+        Some(plan)
+    }
+}
+
+impl FilterPlan<'_> {
+    /// 判断该行是否保留，无法判断时保留并交由服务器处理
+    pub fn keep(&self, row: &[String]) -> bool {
+        // 空单元格视为无法判断
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|i| row.get(i))
+                .map(|v| v.trim().to_lowercase())
+                .filter(|v| !v.is_empty())
+        };
+
+        let is_invention = match cell(self.type_index) {
+            Some(value) => Some(self.filter.invention_values.contains(&value)),
+            None => cell(self.number_index)
+                .and_then(|number| application_number::patent_type(&number))
+                .map(|patent_type| patent_type.is_invention()),
+        };
+        if is_invention == Some(false) {
+            return false;
+        }
+
+        if self.filter.mode == ImportMode::ValidInventionOnly {
+            if let Some(status) = cell(self.status_index) {
+                return self.filter.valid_values.contains(&status);
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(mode: ImportMode, type_column: Option<&str>) -> RowFilter {
+        RowFilter::new(&ImportFilter {
+            mode,
+            type_column: type_column.map(str::to_string),
+            ..ImportFilter::default()
+        })
+    }
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn row(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn invention_only_by_type_column() {
+        let filter = filter(ImportMode::InventionOnly, Some("专利类型"));
+        let plan = filter.plan(&headers(&["申请号", "专利类型"]), "Sheet1").unwrap();
+
+        assert!(plan.keep(&row(&["", "发明"])));
+        assert!(plan.keep(&row(&["", " Invention "])));
+        assert!(!plan.keep(&row(&["", "实用新型"])));
+        // 类型为空时无法判断，保留
+        assert!(plan.keep(&row(&["", ""])));
+        assert!(plan.keep(&row(&["", "  "])));
+        // 类型为空时按申请号推断
+        assert!(!plan.keep(&row(&["CN201320123456.7", ""])));
+    }
+
+    #[test]
+    fn invention_only_by_application_number() {
+        let filter = filter(ImportMode::InventionOnly, None);
+        let plan = filter.plan(&headers(&["申请号"]), "Sheet1").unwrap();
+
+        assert!(plan.keep(&row(&["CN201310123456.X"])));
+        assert!(!plan.keep(&row(&["CN201320123456.7"])));
+        assert!(plan.keep(&row(&[""])));
+        assert!(plan.keep(&row(&["未知"])));
+        assert!(plan.keep(&row(&[])));
+    }
+
+    #[test]
+    fn valid_invention_only() {
+        let filter = filter(ImportMode::ValidInventionOnly, Some("专利类型"));
+        let plan = filter.plan(&headers(&["专利类型", "法律状态"]), "Sheet1").unwrap();
+
+        assert!(plan.keep(&row(&["发明", "有效"])));
+        assert!(!plan.keep(&row(&["发明", "失效"])));
+        assert!(!plan.keep(&row(&["实用新型", "有效"])));
+        // 法律状态为空时无法判断，保留
+        assert!(plan.keep(&row(&["发明", ""])));
+        assert!(plan.keep(&row(&["", ""])));
+        assert!(plan.keep(&row(&["", "有效"])));
+        assert!(!plan.keep(&row(&["", "失效"])));
+    }
+
+    #[test]
+    fn all_mode_has_no_plan() {
+        let filter = filter(ImportMode::All, Some("专利类型"));
+        assert!(filter.plan(&headers(&["专利类型"]), "Sheet1").is_none());
+    }
+}
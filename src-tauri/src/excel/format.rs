@@ -0,0 +1,236 @@
+use calamine::{Data, Range, Reader, Sheets, Xls, Xlsx};
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+use crate::commands::types::FileFormat;
+use super::{archive, text};
+
+/// ZIP 文件头（xlsx）
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+/// OLE2 复合文档文件头（xls）
+const OLE2_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+/// 判断格式需要读取的文件头长度
+pub(crate) const HEADER_SIZE: u64 = OLE2_MAGIC.len() as u64;
+
+/// 文件类型检测结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Detection {
+    /// Excel 工作簿
+    Workbook(FileFormat),
+    /// 扩展名是 Excel，但内容不是可读取的工作簿
+    Rejected(String),
+    /// 不是 Excel 文件
+    Other,
+}
+
+/// 打开的工作簿，CSV/TSV 文件视为只有一个工作表
+pub(crate) enum Workbook {
+    Excel(Sheets<WorkbookReader>),
+    Text {
+        sheet_name: String,
+        range: Range<Data>,
+    },
+}
+
+impl Workbook {
+    pub fn sheet_names(&self) -> Vec<String> {
+        match self {
+            Workbook::Excel(sheets) => sheets.sheet_names(),
+            Workbook::Text { sheet_name, .. } => vec![sheet_name.clone()],
+        }
+    }
+
+    pub fn worksheet_range(&mut self, name: &str) -> Result<Range<Data>, Box<dyn std::error::Error + Send + Sync>> {
+        match self {
+            Workbook::Excel(sheets) => Ok(sheets.worksheet_range(name)?),
+            Workbook::Text { sheet_name, range } if sheet_name == name => Ok(range.clone()),
+            Workbook::Text { .. } => Err(format!("工作表不存在: {}", name).into()),
+        }
+    }
+}
+
+/// 工作簿数据来源，磁盘文件按需读取，压缩包内的文件读入内存
+pub(crate) enum WorkbookReader {
+    File(BufReader<File>),
+    Memory(Cursor<Vec<u8>>),
+}
+
+impl Read for WorkbookReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            WorkbookReader::File(reader) => reader.read(buf),
+            WorkbookReader::Memory(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl Seek for WorkbookReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            WorkbookReader::File(reader) => reader.seek(pos),
+            WorkbookReader::Memory(reader) => reader.seek(pos),
+        }
+    }
+}
+
+/// 检测文件是否为 Excel 工作簿或 CSV/TSV 文件
+///
+/// 只检查扩展名为 xlsx/xls/csv/tsv（不区分大小写）或没有扩展名的文件，按文件头判断实际格式，
+/// 因此扩展名与内容不符的工作簿也能按正确的格式读取。压缩包内的文件同样适用。
+pub fn detect(path: &Path) -> Detection {
+    let Some(expected) = expected_format(path) else {
+        return Detection::Other;
+    };
+
+    let sniffed = match archive::virtual_parts(&path.to_string_lossy()) {
+        Some(_) => archive::read_input(&path.to_string_lossy())
+            .map_err(SniffError::Io)
+            .and_then(|bytes| sniff(Cursor::new(bytes))),
+        None => File::open(path).map_err(SniffError::Io).and_then(sniff),
+    };
+    classify(path, expected, sniffed)
+}
+
+/// 检测已读入内存的文件
+pub(crate) fn detect_bytes(path: &Path, bytes: &[u8]) -> Detection {
+    match expected_format(path) {
+        Some(expected) => classify(path, expected, sniff(Cursor::new(bytes))),
+        None => Detection::Other,
+    }
+}
+
+/// 只按文件头检测，用于扫描压缩包时避免读取全部内容；xlsx 内部的结构在打开时再检查
+pub(crate) fn detect_header(path: &Path, header: &[u8]) -> Detection {
+    match expected_format(path) {
+        Some(expected) => classify(path, expected, Ok(sniff_header(header))),
+        None => Detection::Other,
+    }
+}
+
+/// 按检测到的格式打开工作簿
+pub(crate) fn open_workbook(path: &str) -> Result<Workbook, Box<dyn std::error::Error + Send + Sync>> {
+    let (mut reader, detection) = if archive::virtual_parts(path).is_some() {
+        let bytes = archive::read_input(path)?;
+        let detection = detect_bytes(Path::new(path), &bytes);
+        (WorkbookReader::Memory(Cursor::new(bytes)), detection)
+    } else {
+        let detection = detect(Path::new(path));
+        (WorkbookReader::File(BufReader::new(File::open(path)?)), detection)
+    };
+
+    match detection {
+        Detection::Workbook(FileFormat::Xls) => Ok(Workbook::Excel(Sheets::Xls(Xls::new(reader)?))),
+        Detection::Workbook(format @ (FileFormat::Csv | FileFormat::Tsv)) => {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+            let delimiter = if format == FileFormat::Tsv { b'\t' } else { b',' };
+            Ok(Workbook::Text {
+                sheet_name: Path::new(path)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                range: text::read_table(&bytes, delimiter)?,
+            })
+        }
+        Detection::Workbook(_) => Ok(Workbook::Excel(Sheets::Xlsx(Xlsx::new(reader)?))),
+        Detection::Rejected(reason) => Err(reason.into()),
+        Detection::Other => Err(format!("不是 Excel 文件: {}", path).into()),
+    }
+}
+
+/// 格式对应的扩展名
+pub(crate) fn format_extension(format: FileFormat) -> &'static str {
+    match format {
+        FileFormat::Xls => "xls",
+        FileFormat::Csv => "csv",
+        FileFormat::Tsv => "tsv",
+        _ => "xlsx",
+    }
+}
+
+/// 是否可能是可读取的文件（扩展名为 xlsx/xls/csv/tsv 或没有扩展名）
+pub(crate) fn is_candidate(path: &Path) -> bool {
+    expected_format(path).is_some()
+}
+
+/// 扩展名对应的格式：没有扩展名时为 Some(None)，不需要检测的文件为 None
+fn expected_format(path: &Path) -> Option<Option<FileFormat>> {
+    match path.extension().map(|e| e.to_string_lossy().to_lowercase()).as_deref() {
+        Some("xlsx") => Some(Some(FileFormat::Xlsx)),
+        Some("xls") => Some(Some(FileFormat::Xls)),
+        Some("csv") => Some(Some(FileFormat::Csv)),
+        Some("tsv") => Some(Some(FileFormat::Tsv)),
+        None => Some(None),
+        Some(_) => None,
+    }
+}
+
+fn classify(path: &Path, expected: Option<FileFormat>, sniffed: Result<Option<FileFormat>, SniffError>) -> Detection {
+    match sniffed {
+        Ok(Some(format)) => {
+            if expected.is_some_and(|expected| expected != format) {
+                tracing::debug!("文件扩展名与内容不符，按 {} 读取: {}", format_extension(format), path.display());
+            }
+            Detection::Workbook(format)
+        }
+        // CSV/TSV 没有文件头，按扩展名识别
+        Ok(None) => match expected {
+            Some(format @ (FileFormat::Csv | FileFormat::Tsv)) => Detection::Workbook(format),
+            Some(_) => Detection::Rejected(
+                "文件内容不是 Excel 工作簿（可能是 CSV、HTML 等文件改了扩展名）".to_string(),
+            ),
+            None => Detection::Other,
+        },
+        Err(SniffError::NotWorkbook(reason)) if expected.is_some() => Detection::Rejected(reason),
+        Err(SniffError::Io(e)) if expected.is_some() => Detection::Rejected(format!("无法读取文件: {}", e)),
+        _ => Detection::Other,
+    }
+}
+
+enum SniffError {
+    Io(io::Error),
+    NotWorkbook(String),
+}
+
+impl From<io::Error> for SniffError {
+    fn from(e: io::Error) -> Self {
+        SniffError::Io(e)
+    }
+}
+
+/// 读取文件头判断格式，不是工作簿时返回 None
+fn sniff<R: Read + Seek>(mut reader: R) -> Result<Option<FileFormat>, SniffError> {
+    let mut header = Vec::with_capacity(OLE2_MAGIC.len());
+    (&mut reader).take(HEADER_SIZE).read_to_end(&mut header)?;
+
+    match sniff_header(&header) {
+        Some(FileFormat::Xlsx) => {}
+        format => return Ok(format),
+    }
+
+    // 按 ZIP 中央目录中的文件名判断，不解压其中的文件
+    let archive = zip::ZipArchive::new(reader)
+        .map_err(|e| SniffError::NotWorkbook(format!("文件是损坏的 ZIP 压缩包: {}", e)))?;
+    let contains = |name: &str| archive.file_names().any(|entry| entry == name);
+
+    if contains("xl/workbook.xml") {
+        Ok(Some(FileFormat::Xlsx))
+    } else if contains("xl/workbook.bin") {
+        Err(SniffError::NotWorkbook("不支持 xlsb 格式的工作簿，请另存为 xlsx".to_string()))
+    } else if contains("word/document.xml") {
+        Err(SniffError::NotWorkbook("文件内容是 Word 文档，不是 Excel 工作簿".to_string()))
+    } else {
+        Err(SniffError::NotWorkbook("文件是 ZIP 压缩包，但其中没有 Excel 工作簿".to_string()))
+    }
+}
+
+/// 按文件头判断格式，ZIP 文件头视为 xlsx，不是工作簿时返回 None
+fn sniff_header(header: &[u8]) -> Option<FileFormat> {
+    if header.starts_with(OLE2_MAGIC) {
+        Some(FileFormat::Xls)
+    } else if header.starts_with(ZIP_MAGIC) {
+        Some(FileFormat::Xlsx)
+    } else {
+        None
+    }
+}
@@ -1,71 +1,470 @@
-use calamine::{open_workbook, Data, Reader, Xlsx};
-use std::collections::HashMap;
+pub mod application_number;
+pub mod archive;
+mod computed;
+mod consolidate;
+mod duplicates;
+mod filter;
+pub mod format;
+mod preview;
+mod report;
+mod scan;
+mod schema;
+mod script;
+mod sheets;
+mod suggest;
+#[cfg(test)]
+mod testing;
+mod text;
+mod transform;
+mod validate;
+mod writer;
+
+use calamine::Data;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use walkdir::WalkDir;
-use crate::commands::types::{ColumnMapping, ScanResult};
+use std::sync::Arc;
+use crate::commands::types::{
+    ColumnMapping, ConsolidateConfig, ConvertConfig, ConvertedFile, ConvertedSheet, DuplicateReport, DuplicateRow,
+    FileEntry, FileFormat, MappingPreview, MappingSuggestions, OutputFormat, RejectedFile, RowError, RowStatus,
+    ScanOptions, ScanResult, ScriptErrorAction, SkippedSheet, SplitConfig, TextEncoding, ValidationSchema,
+    WorkbookPreview,
+};
+use computed::ColumnComputer;
+use consolidate::{Consolidator, MergedOutput};
+use duplicates::RemovedRows;
+use format::Detection;
+use filter::RowFilter;
+use script::RowScript;
+use sheets::SheetFilter;
+use transform::ValueTransformer;
+use writer::{CollectedSheet, OutputWriter};
+
+pub use preview::DEFAULT_PREVIEW_ROWS;
+pub use report::write_run_report;
+pub use schema::{apply_schema, check_mappings, check_schema, load_schema};
+pub use suggest::{best_mappings, check_min_score, DEFAULT_MIN_SCORE};
+pub use validate::{validate_application_numbers, validate_files, write_validation_report};
 
 /// 扫描目录中的所有 Excel 文件
-pub async fn scan_directory(source_path: &str) -> Result<ScanResult, Box<dyn std::error::Error + Send + Sync>> {
+pub async fn scan_directory(
+    source_path: &str,
+    options: &ScanOptions,
+) -> Result<ScanResult, Box<dyn std::error::Error + Send + Sync>> {
     tracing::info!("开始扫描目录: {}", source_path);
     
-    let mut files = Vec::new();
+    let mut entries = Vec::new();
+    let mut rejected_files = Vec::new();
     let mut total_size = 0u64;
 
-    for entry in WalkDir::new(source_path) {
+    let filter = scan::PathFilter::new(Path::new(source_path), options)?;
+
+    for entry in filter.walk(options) {
         let entry = entry?;
         let path = entry.path();
-        
-        if let Some(extension) = path.extension() {
-            if extension == "xlsx" || extension == "xls" {
-                if let Ok(metadata) = entry.metadata() {
-                    total_size += metadata.len();
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let modified = entry.metadata().ok().and_then(|m| m.modified().ok());
+
+        if options.archives && archive::is_archive(path) {
+            match archive::list_workbooks(path, &filter, options.nested_archives) {
+                Ok((workbooks, rejected)) => {
+                    for workbook in workbooks {
+                        total_size += workbook.size;
+                        entries.push(scan::file_entry(workbook.path, workbook.size, modified, workbook.format));
+                    }
+                    rejected_files.extend(rejected);
+                }
+                Err(e) => {
+                    let path = path.to_string_lossy().to_string();
+                    tracing::warn!("读取压缩包失败 {}: {}", path, e);
+                    rejected_files.push(RejectedFile {
+                        path,
+                        reason: format!("无法读取压缩包: {}", e),
+                    });
                 }
-                let file_path = path.to_string_lossy().to_string();
-                tracing::debug!("发现 Excel 文件: {}", file_path);
-                files.push(file_path);
             }
+            continue;
         }
+
+        if !filter.allow_file(path) {
+            continue;
+        }
+        
+        match format::detect(path) {
+            Detection::Workbook(file_format) => {
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                let file_entry = scan::file_entry(path.to_string_lossy().to_string(), size, modified, file_format);
+                total_size += file_entry.size;
+                tracing::debug!("发现 Excel 文件: {}", file_entry.path);
+                entries.push(file_entry);
+            }
+            Detection::Rejected(reason) => {
+                let path = path.to_string_lossy().to_string();
+                tracing::warn!("跳过文件 {}: {}", path, reason);
+                rejected_files.push(RejectedFile { path, reason });
+            }
+            Detection::Other => {}
+        }
+    }
+
+    // 检测内容相同的文件
+    let duplicate_groups = if options.hash || options.skip_duplicates {
+        let candidates: Vec<(String, u64)> = entries.iter().map(|e| (e.path.clone(), e.size)).collect();
+        tokio::task::spawn_blocking(move || scan::find_duplicate_files(&candidates)).await??
+    } else {
+        Vec::new()
+    };
+
+    for group in &duplicate_groups {
+        tracing::warn!("内容相同的文件 ({} 个): {}", group.files.len(), group.files.join(", "));
+    }
+
+    if options.skip_duplicates {
+        let skipped: HashSet<&String> = duplicate_groups.iter().flat_map(|g| g.files.iter().skip(1)).collect();
+        entries.retain(|entry| {
+            if skipped.contains(&entry.path) {
+                tracing::info!("跳过内容重复的文件: {}", entry.path);
+                total_size -= entry.size;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    if options.inspect {
+        entries = inspect_entries(entries, &options.mappings).await?;
     }
 
-    tracing::info!("扫描完成，共发现 {} 个文件，总大小: {} bytes", files.len(), total_size);
+    tracing::info!("扫描完成，共发现 {} 个文件，总大小: {} bytes", entries.len(), total_size);
 
     Ok(ScanResult {
-        file_count: files.len(),
+        file_count: entries.len(),
         total_size,
-        files,
+        files: entries.iter().map(|e| e.path.clone()).collect(),
+        entries,
+        duplicate_groups,
+        rejected_files,
     })
 }
 
-/// 转换 Excel 文件
-pub async fn convert_files(
-    source_path: &str,
-    target_path: &str,
+/// 读取文件的工作表、表头和行数，供界面在扫描后按需加载
+pub async fn inspect_files(
+    files: &[String],
     mappings: &[ColumnMapping],
-) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
-    tracing::info!("开始转换文件，源路径: {}, 目标路径: {}, 映射规则数: {}", source_path, target_path, mappings.len());
-    
-    // 创建映射 HashMap
-    let mapping_map: HashMap<String, String> = mappings
+) -> Result<Vec<FileEntry>, Box<dyn std::error::Error + Send + Sync>> {
+    let entries = files
         .iter()
-        .map(|m| {
-            tracing::debug!("列映射: {} -> {}", m.original, m.mapped);
-            (m.original.clone(), m.mapped.clone())
+        .map(|file| {
+            let path = Path::new(file);
+            let metadata = std::fs::metadata(archive::physical_path(file)).ok();
+            let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+            let size = match archive::virtual_parts(file) {
+                Some(_) => archive::read_input(file).map(|bytes| bytes.len() as u64).unwrap_or(0),
+                None => metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+            };
+            let entry = |format| scan::file_entry(file.clone(), size, modified, format);
+            match format::detect(path) {
+                Detection::Workbook(file_format) => entry(file_format),
+                Detection::Rejected(reason) => FileEntry {
+                    error: Some(reason),
+                    ..entry(FileFormat::Unknown)
+                },
+                Detection::Other => FileEntry {
+                    error: Some("不是 Excel 文件".to_string()),
+                    ..entry(FileFormat::Unknown)
+                },
+            }
         })
         .collect();
 
+    inspect_entries(entries, mappings).await
+}
+
+async fn inspect_entries(
+    entries: Vec<FileEntry>,
+    mappings: &[ColumnMapping],
+) -> Result<Vec<FileEntry>, Box<dyn std::error::Error + Send + Sync>> {
+    let mappings = mappings.to_vec();
+    let entries = tokio::task::spawn_blocking(move || {
+        entries
+            .into_iter()
+            .map(|mut entry| {
+                scan::inspect_entry(&mut entry, &mappings);
+                entry
+            })
+            .collect()
+    })
+    .await?;
+
+    Ok(entries)
+}
+
+/// 编译后的转换配置
+struct Converter {
+    mappings: HashMap<String, String>,
+    sheet_filter: SheetFilter,
+    transformer: ValueTransformer,
+    computer: ColumnComputer,
+    script: Option<RowScript>,
+    import_filter: Option<RowFilter>,
+    output_format: OutputFormat,
+    text_encoding: TextEncoding,
+    split: Option<SplitConfig>,
+    consolidate: Option<ConsolidateConfig>,
+    dry_run: bool,
+    required_columns: Vec<String>,
+}
+
+impl Converter {
+    fn new(config: &ConvertConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        // 创建映射 HashMap
+        let mappings = config
+            .mappings
+            .iter()
+            .map(|m| {
+                tracing::debug!("列映射: {} -> {}", m.original, m.mapped);
+                (m.original.clone(), m.mapped.clone())
+            })
+            .collect();
+        if let Some(split) = &config.split {
+            split.check()?;
+        }
+
+        Ok(Self {
+            mappings,
+            sheet_filter: SheetFilter::new(&config.sheets)?,
+            transformer: ValueTransformer::new(&config.transforms, &config.lookup_tables)?,
+            computer: ColumnComputer::new(&config.computed_columns)?,
+            script: config.script.as_ref().map(RowScript::load).transpose()?,
+            import_filter: config.import_filter.as_ref().map(RowFilter::new),
+            output_format: config.output_format,
+            text_encoding: config.text_encoding,
+            split: config.split.clone(),
+            consolidate: config.consolidate.clone(),
+            dry_run: config.dry_run,
+            required_columns: config.required_columns.clone(),
+        })
+    }
+
+    /// 获取映射后的列名
+    fn map_header<'a>(&'a self, header: &'a str) -> &'a str {
+        self.mappings.get(header).map(String::as_str).unwrap_or(header)
+    }
+
+    /// 区分应用了映射规则的源列和按原名输出的源列，空列名忽略
+    fn header_mappings(&self, original_headers: &[String]) -> (Vec<ColumnMapping>, Vec<String>) {
+        let mut mapped = Vec::new();
+        let mut unmapped = Vec::new();
+        for original in original_headers {
+            match self.mappings.get(original) {
+                Some(target) => mapped.push(ColumnMapping {
+                    original: original.clone(),
+                    mapped: target.clone(),
+                }),
+                None if !original.is_empty() => unmapped.push(original.clone()),
+                None => {}
+            }
+        }
+        (mapped, unmapped)
+    }
+
+    /// 输出表头中缺少的必需列
+    fn missing_columns(&self, headers: &[String]) -> Vec<String> {
+        self.required_columns
+            .iter()
+            .filter(|column| !headers.contains(column))
+            .cloned()
+            .collect()
+    }
+
+    /// 没有匹配到任何源列的列映射；源文件已使用目标列名时不计入
+    fn unmatched_mappings(&self, original_headers: &HashSet<String>) -> Vec<ColumnMapping> {
+        let mut unmatched: Vec<ColumnMapping> = self
+            .mappings
+            .iter()
+            .filter(|(original, mapped)| !original_headers.contains(*original) && !original_headers.contains(*mapped))
+            .map(|(original, mapped)| ColumnMapping {
+                original: original.clone(),
+                mapped: mapped.clone(),
+            })
+            .collect();
+        unmatched.sort_by(|a, b| a.original.cmp(&b.original));
+        unmatched
+    }
+
+    /// 按映射后的表头解析工作表的列值转换、计算列和过滤规则，计算列追加到表头末尾
+    fn plan_sheet(&self, mut headers: Vec<String>, file_name: &str, sheet_name: &str) -> SheetPlan<'_> {
+        let column_steps = self.transformer.plan(&headers);
+        let computed = self.computer.plan(&mut headers, file_name, sheet_name);
+        let filter = self
+            .import_filter
+            .as_ref()
+            .and_then(|filter| filter.plan(&headers, sheet_name));
+
+        SheetPlan {
+            headers,
+            column_steps,
+            computed,
+            filter,
+        }
+    }
+
+    /// 处理一行数据：列值转换、计算列、脚本和本地过滤
+    ///
+    /// 脚本执行失败且 `on_error` 为 abort 时返回错误。
+    fn convert_row(
+        &self,
+        plan: &SheetPlan,
+        row: &[Data],
+        sheet_name: &str,
+        source_row: usize,
+    ) -> Result<RowResult, String> {
+        let mut values: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(col_index, cell)| {
+                let cell_text = cell_to_string(cell);
+                match plan.column_steps.get(col_index) {
+                    Some(Some(steps)) => transform::apply_steps(steps, cell_text),
+                    _ => cell_text,
+                }
+            })
+            .collect();
+        plan.computed.apply(&mut values);
+
+        let mut error = None;
+        if let Some(script) = &self.script {
+            match script.run(&plan.headers, &mut values) {
+                Ok(true) => {}
+                Ok(false) => {
+                    return Ok(RowResult {
+                        values,
+                        status: RowStatus::ScriptSkipped,
+                        error,
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!("工作表 {} 第 {} 行脚本执行失败: {}", sheet_name, source_row, e);
+                    if script.on_error() == ScriptErrorAction::Abort {
+                        return Err(format!("工作表 {} 第 {} 行脚本执行失败: {}", sheet_name, source_row, e));
+                    }
+                    let skip = script.on_error() == ScriptErrorAction::Skip;
+                    error = Some(e);
+                    if skip {
+                        return Ok(RowResult {
+                            values,
+                            status: RowStatus::Failed,
+                            error,
+                        });
+                    }
+                }
+            }
+        }
+
+        let status = match &plan.filter {
+            Some(filter) if !filter.keep(&values) => RowStatus::Filtered,
+            _ => RowStatus::Kept,
+        };
+        Ok(RowResult { values, status, error })
+    }
+}
+
+/// 工作表的转换计划
+struct SheetPlan<'a> {
+    /// 输出的表头（映射后，包含计算列）
+    headers: Vec<String>,
+    column_steps: Vec<Option<&'a [transform::Step]>>,
+    computed: computed::ComputedPlan,
+    filter: Option<filter::FilterPlan<'a>>,
+}
+
+/// 单行的处理结果
+struct RowResult {
+    values: Vec<String>,
+    status: RowStatus,
+    /// 脚本执行失败的原因，按 `on_error` 保留时状态仍为 kept
+    error: Option<String>,
+}
+
+/// 读取转换配置文件
+///
+/// 配置中的相对路径（如脚本文件）相对于配置文件所在目录，命令行和界面读取配置时都经过这里。
+pub fn load_convert_config(path: &str) -> Result<ConvertConfig, Box<dyn std::error::Error + Send + Sync>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取转换配置文件失败 {}: {}", path, e))?;
+    let mut config: ConvertConfig = serde_json::from_str(&content)
+        .map_err(|e| format!("解析转换配置文件失败 {}: {}", path, e))?;
+
+    let config_dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+    if let Some(script) = config.script.as_mut() {
+        if Path::new(&script.path).is_relative() {
+            script.path = config_dir.join(&script.path).to_string_lossy().to_string();
+        }
+    }
+
+    tracing::info!("读取转换配置: {}", path);
+    Ok(config)
+}
+
+/// 转换 Excel 文件
+pub async fn convert_files(
+    source_path: &str,
+    target_path: &str,
+    config: &ConvertConfig,
+) -> Result<Vec<ConvertedFile>, Box<dyn std::error::Error + Send + Sync>> {
     // 扫描文件
-    let scan_result = scan_directory(source_path).await?;
+    let scan_result = scan_directory(source_path, &config.scan).await?;
+    convert_paths(&scan_result.files, source_path, target_path, config).await
+}
+
+/// 转换指定的文件，文件须位于 `source_path` 下，输出时保持相对于它的目录结构
+pub async fn convert_paths(
+    files: &[String],
+    source_path: &str,
+    target_path: &str,
+    config: &ConvertConfig,
+) -> Result<Vec<ConvertedFile>, Box<dyn std::error::Error + Send + Sync>> {
+    tracing::info!("开始转换文件，源路径: {}, 目标路径: {}, 映射规则数: {}", source_path, target_path, config.mappings.len());
+    
+    let converter = Arc::new(Converter::new(config)?);
     let mut converted_files = Vec::new();
+    let mut consolidator = config
+        .consolidate
+        .as_ref()
+        .map(|consolidate| {
+            Consolidator::new(consolidate, Path::new(target_path), config.output_format, config.text_encoding)
+        });
+
+    // 检测跨文件的重复专利
+    let mut duplicate_rows = match &config.duplicates {
+        Some(duplicate_config) => {
+            let report = duplicates::build_report(files, &converter, duplicate_config).await?;
+            duplicates::rows_by_file(&report, duplicate_config.remove)
+        }
+        None => HashMap::new(),
+    };
 
-    tracing::info!("准备转换 {} 个文件", scan_result.files.len());
+    tracing::info!("准备转换 {} 个文件", files.len());
 
     // 转换每个文件
-    for (index, file_path) in scan_result.files.iter().enumerate() {
-        tracing::info!("正在转换文件 {}/{}: {}", index + 1, scan_result.files.len(), file_path);
-        match convert_single_file(file_path, source_path, target_path, &mapping_map).await {
-            Ok(converted_path) => {
-                tracing::info!("文件转换成功: {}", converted_path);
-                converted_files.push(converted_path);
+    for (index, file_path) in files.iter().enumerate() {
+        tracing::info!("正在转换文件 {}/{}: {}", index + 1, files.len(), file_path);
+        let file_duplicates = duplicate_rows.remove(file_path).unwrap_or_default();
+        match convert_single_file(file_path, source_path, target_path, &converter, file_duplicates).await {
+            Ok((converted, held)) => {
+                let file_index = converted_files.len();
+                converted_files.push(converted);
+                match (consolidator.as_mut(), held) {
+                    (Some(consolidator), Some(sheets)) => {
+                        tracing::info!("文件转换成功，等待合并: {}", file_path);
+                        for merged in consolidator.add(file_index, sheets) {
+                            write_merged(merged, &mut converted_files, converter.dry_run).await?;
+                        }
+                    }
+                    _ => tracing::info!("文件转换成功: {}", converted_files[file_index].target),
+                }
             }
             Err(e) => {
                 tracing::error!("文件转换失败 {}: {}", file_path, e);
@@ -74,18 +473,122 @@ pub async fn convert_files(
         }
     }
 
+    if let Some(consolidator) = consolidator {
+        for merged in consolidator.finish() {
+            write_merged(merged, &mut converted_files, converter.dry_run).await?;
+        }
+    }
+
     tracing::info!("所有文件转换完成，共 {} 个文件", converted_files.len());
 
     Ok(converted_files)
 }
 
+/// 预览工作簿中某个工作表的前若干行，未指定工作表时预览第一个
+pub async fn preview_workbook(
+    path: &str,
+    sheet: Option<&str>,
+    limit: usize,
+) -> Result<WorkbookPreview, Box<dyn std::error::Error + Send + Sync>> {
+    let path = path.to_string();
+    let sheet = sheet.map(str::to_string);
+    tokio::task::spawn_blocking(move || preview::preview_workbook(&path, sheet.as_deref(), limit)).await?
+}
+
+/// 预览前若干行按当前列映射、转换、计算列、脚本和过滤规则处理后的结果
+pub async fn preview_mapping(
+    path: &str,
+    sheet: Option<&str>,
+    limit: usize,
+    config: &ConvertConfig,
+) -> Result<MappingPreview, Box<dyn std::error::Error + Send + Sync>> {
+    let converter = Converter::new(config)?;
+    let path = path.to_string();
+    let sheet = sheet.map(str::to_string);
+    tokio::task::spawn_blocking(move || preview::preview_mapping(&path, sheet.as_deref(), limit, &converter)).await?
+}
+
+/// 检查转换配置能否用于转换：工作表规则、列值转换、计算列模板、行处理脚本和拆分上限
+pub fn check_convert_config(config: &ConvertConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    Converter::new(config).map(|_| ())
+}
+
+/// 统计文件中所有不同的表头，按精确、规范化、拼音和模糊匹配给出到目标列的映射建议
+pub async fn suggest_mappings(
+    files: &[String],
+    targets: &[String],
+    schema: Option<&ValidationSchema>,
+    min_score: f64,
+) -> Result<MappingSuggestions, Box<dyn std::error::Error + Send + Sync>> {
+    check_min_score(min_score)?;
+    let files = files.to_vec();
+    let targets = targets.to_vec();
+    let schema = schema.cloned();
+    Ok(tokio::task::spawn_blocking(move || {
+        suggest::suggest_mappings(&files, &targets, schema.as_ref(), min_score)
+    })
+    .await?)
+}
+
+/// 检测文件中重复的专利，识别列和保留规则取自 `config.duplicates`
+pub async fn find_duplicates(
+    files: &[String],
+    config: &ConvertConfig,
+) -> Result<DuplicateReport, Box<dyn std::error::Error + Send + Sync>> {
+    let converter = Arc::new(Converter::new(config)?);
+    let duplicate_config = config.duplicates.clone().unwrap_or_default();
+    duplicates::build_report(files, &converter, &duplicate_config).await
+}
+
+/// 写出合并文件，并将合并结果记录到各源文件的转换结果中；试运行时只记录预计的合并文件
+async fn write_merged(
+    merged: MergedOutput,
+    converted_files: &mut [ConvertedFile],
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (merged, outputs) = if dry_run {
+        let outputs = vec![merged.path.clone()];
+        (merged, outputs)
+    } else {
+        if let Some(parent) = merged.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::task::spawn_blocking(move || {
+            let outputs = merged.write()?;
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>((merged, outputs))
+        })
+        .await??
+    };
+
+    for (file_index, range) in merged.ranges {
+        let Some(converted) = converted_files.get_mut(file_index) else {
+            continue;
+        };
+        for output in &outputs {
+            let output = output.to_string_lossy().to_string();
+            if !converted.outputs.contains(&output) {
+                converted.outputs.push(output);
+            }
+        }
+        if converted.target.is_empty() {
+            converted.target = converted.outputs.first().cloned().unwrap_or_default();
+        }
+        converted.merged_rows.push(range);
+    }
+    Ok(())
+}
+
 /// 转换单个 Excel 文件
+///
+/// 启用合并时先将转换结果收集到内存，数据行数不超过合并上限的文件返回收集到的工作表，由调用方合并输出。
+/// 试运行时同样只在内存中转换，输出路径为预计的路径。
 async fn convert_single_file(
     file_path: &str,
     source_root: &str,
     target_root: &str,
-    mappings: &HashMap<String, String>,
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    converter: &Arc<Converter>,
+    duplicate_rows: Vec<DuplicateRow>,
+) -> Result<(ConvertedFile, Option<Vec<CollectedSheet>>), Box<dyn std::error::Error + Send + Sync>> {
     tracing::debug!("转换单个文件: {}", file_path);
     
     // 计算目标文件路径，保持目录结构
@@ -96,107 +599,303 @@ async fn convert_single_file(
             format!("Invalid path structure: {}", e)
         })?;
     
-    let target_path = Path::new(target_root).join(relative_path);
+    // 压缩包内的文件输出到以压缩包命名的目录下，扩展名由输出格式决定
+    let relative_path = match archive::virtual_parts(file_path) {
+        Some(_) => archive::mirror_path(relative_path),
+        None => relative_path.to_path_buf(),
+    };
+    let target_path = Path::new(target_root)
+        .join(relative_path)
+        .with_extension(converter.output_format.extension());
     
     tracing::debug!("目标文件路径: {}", target_path.display());
     
-    // 确保目标目录存在
-    if let Some(parent) = target_path.parent() {
-        tracing::debug!("创建目标目录: {}", parent.display());
-        tokio::fs::create_dir_all(parent).await?;
+    // 确保目标目录存在，合并输出的文件在写出时再创建
+    if converter.consolidate.is_none() && !converter.dry_run {
+        if let Some(parent) = target_path.parent() {
+            tracing::debug!("创建目标目录: {}", parent.display());
+            tokio::fs::create_dir_all(parent).await?;
+        }
     }
 
     // 克隆数据以便移动到 blocking task 中
-    let file_path = file_path.to_string();
+    let source = file_path.to_string();
     let target_path_buf = target_path.to_path_buf();
-    let target_path_buf_clone = target_path_buf.clone();
-    let mappings = mappings.clone();
+    let converter = Arc::clone(converter);
+    let removed_rows = duplicates::removed_rows(&duplicate_rows);
 
     // 处理 Excel 文件
-    tokio::task::spawn_blocking(move || {
-        process_excel_file(&file_path, &target_path_buf, &mappings)
+    let (mut converted, held) = tokio::task::spawn_blocking(move || {
+        if converter.consolidate.is_none() && !converter.dry_run {
+            let (converted, _) = process_excel_file(&source, Some(&target_path_buf), &converter, &removed_rows)?;
+            return Ok((converted, None));
+        }
+
+        let (mut converted, sheets) = process_excel_file(&source, None, &converter, &removed_rows)?;
+        if converted.skipped.is_some() {
+            return Ok((converted, None));
+        }
+        if converter.consolidate.as_ref().is_some_and(|c| converted.rows_written <= c.max_rows) {
+            return Ok((converted, Some(sheets)));
+        }
+
+        // 试运行时按与写出时相同的拆分和命名规则计算预计的输出文件
+        if converter.consolidate.is_some() {
+            tracing::info!("文件数据行数超过合并上限，单独输出: {}", source);
+        }
+        if !converter.dry_run {
+            if let Some(parent) = target_path_buf.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let outputs: Vec<String> = consolidate::write_collected(
+            &target_path_buf,
+            converter.output_format,
+            converter.text_encoding,
+            converter.split.as_ref(),
+            &sheets,
+            converter.dry_run,
+        )?
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+        converted.target = outputs.first().cloned().unwrap_or_default();
+        converted.outputs = outputs;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>((converted, None))
     }).await??;
+    converted.duplicate_rows = duplicate_rows;
     
-    tracing::debug!("文件处理完成: {}", target_path_buf_clone.display());
+    tracing::debug!("文件处理完成: {}", target_path.display());
     
-    Ok(target_path_buf_clone.to_string_lossy().to_string())
+    Ok((converted, held))
 }
 
-/// 处理 Excel 文件内容
+/// 处理 Excel 文件内容，`target_path` 为空时不写文件，返回收集到的工作表
 fn process_excel_file(
     source_path: &str,
-    target_path: &Path,
-    mappings: &HashMap<String, String>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    tracing::debug!("处理 Excel 文件: {} -> {}", source_path, target_path.display());
+    target_path: Option<&Path>,
+    converter: &Converter,
+    removed_rows: &RemovedRows,
+) -> Result<(ConvertedFile, Vec<CollectedSheet>), Box<dyn std::error::Error + Send + Sync>> {
+    tracing::debug!("处理 Excel 文件: {}", source_path);
     
     // 打开源文件
-    let mut workbook: Xlsx<_> = open_workbook(source_path)
+    let mut workbook = format::open_workbook(source_path)
         .map_err(|e| {
             tracing::error!("打开 Excel 文件失败: {}", e);
             e
         })?;
     
-    // 使用 xlsxwriter 创建新文件
-    let new_workbook = xlsxwriter::Workbook::new(target_path.to_str().unwrap())
-        .map_err(|e| {
-            tracing::error!("创建新 Excel 文件失败: {}", e);
-            e
-        })?;
-    
-    let sheet_names = workbook.sheet_names().to_owned();
+    let sheet_names = workbook.sheet_names();
     tracing::debug!("工作表数量: {}", sheet_names.len());
+
+    // 创建输出文件
+    let mut output = match target_path {
+        Some(target_path) => OutputWriter::create(
+            target_path,
+            converter.output_format,
+            converter.text_encoding,
+            converter.split.as_ref(),
+        )?,
+        None => OutputWriter::collect(),
+    };
+
+    let file_name = Path::new(source_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
     
+    let mut rows_written = 0;
+    let mut filtered_rows = 0;
+    let mut script_skipped_rows = 0;
+    let mut row_errors = Vec::new();
+    let mut skipped_sheets = Vec::new();
+    let mut sheets = Vec::new();
+    let mut original_headers_seen = HashSet::new();
+    let mut skip_sheet = |sheet_name: &str, reason: String| {
+        tracing::info!("跳过工作表 {}: {}", sheet_name, reason);
+        skipped_sheets.push(SkippedSheet {
+            sheet: sheet_name.to_string(),
+            reason,
+        });
+    };
+
     // 处理每个工作表
-    for sheet_name in sheet_names {
+    for (sheet_index, sheet_name) in sheet_names.iter().enumerate() {
         tracing::debug!("处理工作表: {}", sheet_name);
+
+        if let Some(reason) = converter.sheet_filter.check_name(sheet_index, sheet_name) {
+            skip_sheet(sheet_name, reason);
+            continue;
+        }
         
-        if let Ok(range) = workbook.worksheet_range(&sheet_name) {
-            let mut worksheet = new_workbook.add_worksheet(Some(&sheet_name))?;
-            
-            let mut row_index = 0u32;
-            let row_count = range.rows().count();
-            tracing::debug!("工作表 {} 共 {} 行", sheet_name, row_count);
-            
-            // 处理所有行
-            for row in range.rows() {
-                if row_index == 0 {
-                    // 处理表头行
-                    let mut mapped_count = 0;
-                    for (col_index, cell) in row.iter().enumerate() {
-                        let header_text = cell_to_string(cell);
-                        let mapped_header = mappings.get(&header_text).unwrap_or(&header_text);
-                        if mapped_header != &header_text {
-                            mapped_count += 1;
-                            tracing::debug!("映射列名: {} -> {}", header_text, mapped_header);
-                        }
-                        worksheet.write_string(row_index, col_index as u16, mapped_header, None)?;
-                    }
-                    if mapped_count > 0 {
-                        tracing::info!("工作表 {} 应用了 {} 个列映射", sheet_name, mapped_count);
-                    }
-                } else {
-                    // 处理数据行
-                    for (col_index, cell) in row.iter().enumerate() {
-                        let cell_text = cell_to_string(cell);
-                        worksheet.write_string(row_index, col_index as u16, &cell_text, None)?;
-                    }
+        let range = match workbook.worksheet_range(sheet_name) {
+            Ok(range) => range,
+            Err(e) => {
+                skip_sheet(sheet_name, format!("读取工作表失败: {}", e));
+                continue;
+            }
+        };
+
+        let mut rows = range.rows();
+        let header_row = range.start().map(|(row, _)| row as usize).unwrap_or(0);
+        let row_count = range.height();
+        tracing::debug!("工作表 {} 共 {} 行", sheet_name, row_count);
+
+        // 处理表头行
+        let original_headers: Vec<String> = rows
+            .next()
+            .map(|row| row.iter().map(cell_to_string).collect())
+            .unwrap_or_default();
+        let headers: Vec<String> = original_headers
+            .iter()
+            .map(|header| converter.map_header(header).to_string())
+            .collect();
+
+        if let Some(reason) = converter.sheet_filter.check_headers(&headers) {
+            skip_sheet(sheet_name, reason);
+            continue;
+        }
+
+        let (mapped_headers, unmapped_headers) = converter.header_mappings(&original_headers);
+        for mapping in &mapped_headers {
+            tracing::debug!("映射列名: {} -> {}", mapping.original, mapping.mapped);
+        }
+        let mut summary = ConvertedSheet {
+            name: sheet_name.clone(),
+            headers: Vec::new(),
+            rows: 0,
+            mapped_headers,
+            unmapped_headers,
+            missing_columns: Vec::new(),
+        };
+        if !summary.mapped_headers.is_empty() {
+            tracing::info!("工作表 {} 应用了 {} 个列映射", sheet_name, summary.mapped_headers.len());
+        }
+        if !summary.unmapped_headers.is_empty() {
+            tracing::info!("工作表 {} 未映射的列: {}", sheet_name, summary.unmapped_headers.join(", "));
+        }
+        original_headers_seen.extend(original_headers);
+
+        let plan = converter.plan_sheet(headers, &file_name, sheet_name);
+        summary.missing_columns = converter.missing_columns(&plan.headers);
+        if !summary.missing_columns.is_empty() {
+            tracing::warn!("工作表 {} 缺少必需列: {}", sheet_name, summary.missing_columns.join(", "));
+        }
+
+        if output.is_full() {
+            output.next_part()?;
+        }
+        let mut worksheet = output.add_sheet(sheet_name, &plan.headers)?;
+
+        // 处理数据行
+        let sheet_removed_rows = removed_rows.get(sheet_name);
+        for (row_index, row) in rows.enumerate() {
+            // 源文件中的行号，从 1 开始
+            let source_row = header_row + row_index + 2;
+            if sheet_removed_rows.is_some_and(|removed| removed.contains(&source_row)) {
+                tracing::debug!("移除重复行: 工作表 {} 第 {} 行", sheet_name, source_row);
+                continue;
+            }
+
+            let result = converter.convert_row(&plan, row, sheet_name, source_row)?;
+            if let Some(message) = result.error {
+                row_errors.push(RowError {
+                    sheet: sheet_name.clone(),
+                    row: source_row,
+                    message,
+                });
+            }
+            match result.status {
+                RowStatus::Kept => {}
+                RowStatus::ScriptSkipped => {
+                    script_skipped_rows += 1;
+                    continue;
                 }
-                
-                row_index += 1;
+                RowStatus::Filtered => {
+                    filtered_rows += 1;
+                    continue;
+                }
+                RowStatus::Failed => continue,
+            }
+            let values = result.values;
+
+            if worksheet.is_full() {
+                worksheet.finish()?;
+                output.next_part()?;
+                worksheet = output.add_sheet(sheet_name, &plan.headers)?;
+            }
+            worksheet.write_row(source_row, &values)?;
+            rows_written += 1;
+            summary.rows += 1;
+        }
+        worksheet.finish()?;
+        summary.headers = plan.headers;
+        sheets.push(summary);
+    }
+
+    // 所有工作表均被跳过时不生成输出，跳过该文件并在转换结果中说明
+    let skipped = (!sheet_names.is_empty() && skipped_sheets.len() == sheet_names.len())
+        .then(|| "所有工作表均被跳过".to_string());
+    let collected = output.take_collected();
+    let mut outputs: Vec<String> = output
+        .close()?
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    if skipped.is_some() {
+        tracing::warn!("所有工作表均被跳过，跳过文件: {}", source_path);
+        for path in outputs.drain(..) {
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!("删除空的输出文件失败 {}: {}", path, e);
             }
         }
     }
     
-    new_workbook.close()
-        .map_err(|e| {
-            tracing::error!("关闭 Excel 文件失败: {}", e);
-            e
-        })?;
-    
+    if script_skipped_rows > 0 {
+        tracing::info!("脚本跳过了 {} 行", script_skipped_rows);
+    }
+    if converter.import_filter.is_some() {
+        tracing::info!("本地导入过滤: 保留 {} 行, 丢弃 {} 行", rows_written, filtered_rows);
+    }
+    let unmatched_mappings = converter.unmatched_mappings(&original_headers_seen);
+    for mapping in &unmatched_mappings {
+        tracing::warn!("列映射没有匹配到源列: {} -> {} ({})", mapping.original, mapping.mapped, source_path);
+    }
+    let mut unmapped_headers = Vec::new();
+    let mut missing_columns = Vec::new();
+    for sheet in &sheets {
+        for header in &sheet.unmapped_headers {
+            if !unmapped_headers.contains(header) {
+                unmapped_headers.push(header.clone());
+            }
+        }
+        for column in &sheet.missing_columns {
+            if !missing_columns.contains(column) {
+                missing_columns.push(column.clone());
+            }
+        }
+    }
     tracing::debug!("Excel 文件处理完成");
     
-    Ok(())
+    let converted = ConvertedFile {
+        source: source_path.to_string(),
+        target: outputs.first().cloned().unwrap_or_default(),
+        outputs,
+        sheets,
+        skipped_sheets,
+        rows_written,
+        filtered_rows,
+        script_skipped_rows,
+        row_errors,
+        duplicate_rows: Vec::new(),
+        merged_rows: Vec::new(),
+        unmapped_headers,
+        unmatched_mappings,
+        missing_columns,
+        skipped,
+    };
+    Ok((converted, collected))
 }
 
 /// 将 Data 转换为字符串
@@ -216,6 +915,6 @@ fn cell_to_string(cell: &Data) -> String {
 
 /// 获取目录下的所有 Excel 文件路径
 pub async fn get_excel_files(directory: &str) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
-    let scan_result = scan_directory(directory).await?;
+    let scan_result = scan_directory(directory, &ScanOptions::default()).await?;
     Ok(scan_result.files)
 }
\ No newline at end of file
@@ -0,0 +1,153 @@
+use calamine::Data;
+use std::path::Path;
+use crate::commands::types::{
+    CellType, MappedRow, MappingPreview, PreviewCell, PreviewRow, RowStatus, WorkbookPreview,
+};
+use super::{cell_to_string, format, Converter};
+
+/// 默认预览的行数
+pub const DEFAULT_PREVIEW_ROWS: usize = 20;
+
+/// 读取工作表列表和指定工作表的表头、前若干行
+pub(crate) fn preview_workbook(
+    path: &str,
+    sheet: Option<&str>,
+    limit: usize,
+) -> Result<WorkbookPreview, Box<dyn std::error::Error + Send + Sync>> {
+    let mut workbook = format::open_workbook(path)?;
+    let sheets = workbook.sheet_names();
+    let (_, sheet_name) = select_sheet(&sheets, sheet)?;
+    let range = workbook.worksheet_range(&sheet_name)?;
+
+    let header_row = range.start().map(|(row, _)| row as usize);
+    let mut rows = range.rows();
+    let headers = rows
+        .next()
+        .map(|row| row.iter().map(cell_to_string).collect())
+        .unwrap_or_default();
+    let first_row = header_row.unwrap_or(0) + 2;
+
+    let rows = rows
+        .take(limit)
+        .enumerate()
+        .map(|(index, row)| PreviewRow {
+            row: first_row + index,
+            cells: row.iter().map(preview_cell).collect(),
+        })
+        .collect();
+
+    Ok(WorkbookPreview {
+        path: path.to_string(),
+        sheets,
+        sheet: sheet_name,
+        header_row: header_row.map(|row| row + 1),
+        headers,
+        rows,
+        total_rows: range.height().saturating_sub(1),
+    })
+}
+
+/// 按转换配置处理指定工作表的前若干行，不写出文件
+pub(crate) fn preview_mapping(
+    path: &str,
+    sheet: Option<&str>,
+    limit: usize,
+    converter: &Converter,
+) -> Result<MappingPreview, Box<dyn std::error::Error + Send + Sync>> {
+    let mut workbook = format::open_workbook(path)?;
+    let sheets = workbook.sheet_names();
+    let (sheet_index, sheet_name) = select_sheet(&sheets, sheet)?;
+    let range = workbook.worksheet_range(&sheet_name)?;
+
+    let header_row = range.start().map(|(row, _)| row as usize).unwrap_or(0);
+    let mut rows = range.rows();
+    let original_headers: Vec<String> = rows
+        .next()
+        .map(|row| row.iter().map(cell_to_string).collect())
+        .unwrap_or_default();
+    let headers: Vec<String> = original_headers
+        .iter()
+        .map(|header| converter.map_header(header).to_string())
+        .collect();
+
+    let skipped = converter
+        .sheet_filter
+        .check_name(sheet_index, &sheet_name)
+        .or_else(|| converter.sheet_filter.check_headers(&headers));
+    let (mapped_headers, unmapped_headers) = converter.header_mappings(&original_headers);
+
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let plan = converter.plan_sheet(headers, &file_name, &sheet_name);
+
+    let rows = rows
+        .take(limit)
+        .enumerate()
+        .map(|(index, row)| {
+            let source_row = header_row + index + 2;
+            match converter.convert_row(&plan, row, &sheet_name, source_row) {
+                Ok(result) => MappedRow {
+                    row: source_row,
+                    values: result.values,
+                    status: result.status,
+                    error: result.error,
+                },
+                // 预览时脚本出错不中止，转换时会中止
+                Err(message) => MappedRow {
+                    row: source_row,
+                    values: Vec::new(),
+                    status: RowStatus::Failed,
+                    error: Some(message),
+                },
+            }
+        })
+        .collect();
+
+    Ok(MappingPreview {
+        path: path.to_string(),
+        sheet: sheet_name,
+        headers: plan.headers,
+        mapped_headers,
+        unmapped_headers,
+        rows,
+        skipped,
+    })
+}
+
+/// 按名称查找工作表，未指定时取第一个
+fn select_sheet(
+    sheets: &[String],
+    sheet: Option<&str>,
+) -> Result<(usize, String), Box<dyn std::error::Error + Send + Sync>> {
+    match sheet {
+        Some(name) => sheets
+            .iter()
+            .position(|s| s == name)
+            .map(|index| (index, name.to_string()))
+            .ok_or_else(|| format!("工作表不存在: {}", name).into()),
+        None => sheets
+            .first()
+            .map(|name| (0, name.clone()))
+            .ok_or_else(|| "工作簿中没有工作表".into()),
+    }
+}
+
+fn preview_cell(cell: &Data) -> PreviewCell {
+    let cell_type = match cell {
+        Data::Empty => CellType::Empty,
+        Data::String(_) => CellType::String,
+        Data::Int(_) => CellType::Int,
+        Data::Float(_) => CellType::Float,
+        Data::Bool(_) => CellType::Bool,
+        Data::DateTime(_) | Data::DateTimeIso(_) => CellType::DateTime,
+        Data::DurationIso(_) => CellType::Duration,
+        Data::Error(_) => CellType::Error,
+    };
+
+    PreviewCell {
+        value: cell_to_string(cell),
+        cell_type,
+    }
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+use crate::commands::types::{RunReport, UploadReport, UploadStatus};
+
+/// 写出运行报告，按扩展名输出 .json、.xlsx 或 .csv
+///
+/// JSON 和 xlsx 包含运行信息、转换结果和上传结果，CSV 只包含每个文件的上传结果。
+pub fn write_run_report(report: &RunReport, path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    tracing::info!("写出运行报告: {}", path);
+
+    let extension = Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "json" => std::fs::write(path, serde_json::to_string_pretty(report)?)?,
+        "xlsx" => write_report_xlsx(report, path)?,
+        "csv" => write_report_csv(report, path)?,
+        _ => return Err(format!("不支持的报告格式: {}（仅支持 .json、.xlsx 和 .csv）", path).into()),
+    }
+
+    Ok(())
+}
+
+const UPLOAD_HEADERS: [&str; 9] = [
+    "文件", "源文件", "结果", "modifiedCount", "upsertedCount", "excelCount", "尝试次数", "耗时(毫秒)", "错误",
+];
+
+fn write_report_xlsx(report: &RunReport, path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let workbook = xlsxwriter::Workbook::new(path)?;
+
+    let mut summary = workbook.add_worksheet(Some("汇总"))?;
+    let rows = [
+        ("版本", report.version.clone()),
+        ("开始时间", report.started_at.to_rfc3339()),
+        ("结束时间", report.finished_at.to_rfc3339()),
+        ("耗时(毫秒)", report.duration_ms.to_string()),
+        ("服务器", report.server.clone()),
+        ("用户名", report.username.clone()),
+        ("输入目录", report.input.clone()),
+        ("输出目录", report.output.clone()),
+        ("导入模式", report.import_mode.to_string()),
+        ("试运行", report.dry_run.to_string()),
+        ("扫描到的文件", report.files_found.to_string()),
+        ("转换耗时(毫秒)", report.conversion_ms.map(|ms| ms.to_string()).unwrap_or_default()),
+        ("上传成功", report.summary.succeeded.to_string()),
+        ("上传失败", report.summary.failed.to_string()),
+        ("未上传", report.summary.not_uploaded.to_string()),
+        ("modifiedCount", report.summary.modified_count.to_string()),
+        ("upsertedCount", report.summary.upserted_count.to_string()),
+        ("excelCount", report.summary.excel_count.to_string()),
+        ("错误", report.error.clone().unwrap_or_default()),
+        ("退出码", report.exit_code.to_string()),
+    ];
+    for (index, (name, value)) in rows.iter().enumerate() {
+        summary.write_string(index as u32, 0, name, None)?;
+        summary.write_string(index as u32, 1, value, None)?;
+    }
+
+    let mut uploads = workbook.add_worksheet(Some("上传"))?;
+    for (col, header) in UPLOAD_HEADERS.iter().enumerate() {
+        uploads.write_string(0, col as u16, header, None)?;
+    }
+    for (index, upload) in report.uploads.iter().enumerate() {
+        for (col, value) in upload_row(upload).iter().enumerate() {
+            uploads.write_string(index as u32 + 1, col as u16, value, None)?;
+        }
+    }
+
+    let mut conversions = workbook.add_worksheet(Some("转换"))?;
+    let headers = ["源文件", "输出文件", "数据行数", "过滤行数", "脚本跳过行数", "行错误数", "跳过的工作表", "未匹配的列映射", "缺少的必需列"];
+    for (col, header) in headers.iter().enumerate() {
+        conversions.write_string(0, col as u16, header, None)?;
+    }
+    for (index, converted) in report.conversions.iter().enumerate() {
+        let row = index as u32 + 1;
+        let unmatched: Vec<String> = converted
+            .unmatched_mappings
+            .iter()
+            .map(|m| format!("{} -> {}", m.original, m.mapped))
+            .collect();
+        let skipped: Vec<&str> = converted.skipped_sheets.iter().map(|s| s.sheet.as_str()).collect();
+        conversions.write_string(row, 0, &converted.source, None)?;
+        conversions.write_string(row, 1, &converted.outputs.join("\n"), None)?;
+        conversions.write_number(row, 2, converted.rows_written as f64, None)?;
+        conversions.write_number(row, 3, converted.filtered_rows as f64, None)?;
+        conversions.write_number(row, 4, converted.script_skipped_rows as f64, None)?;
+        conversions.write_number(row, 5, converted.row_errors.len() as f64, None)?;
+        conversions.write_string(row, 6, &skipped.join(", "), None)?;
+        conversions.write_string(row, 7, &unmatched.join(", "), None)?;
+        conversions.write_string(row, 8, &converted.missing_columns.join(", "), None)?;
+    }
+
+    workbook.close()?;
+    Ok(())
+}
+
+fn write_report_csv(report: &RunReport, path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(UPLOAD_HEADERS)?;
+    for upload in &report.uploads {
+        writer.write_record(upload_row(upload))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn upload_row(upload: &UploadReport) -> [String; 9] {
+    let count = |value: Option<i64>| value.map(|v| v.to_string()).unwrap_or_default();
+    [
+        upload.file.clone(),
+        upload.sources.join("\n"),
+        status_label(upload.status).to_string(),
+        count(upload.modified_count),
+        count(upload.upserted_count),
+        count(upload.excel_count),
+        upload.attempts.to_string(),
+        upload.duration_ms.to_string(),
+        upload.error.clone().unwrap_or_default(),
+    ]
+}
+
+fn status_label(status: UploadStatus) -> &'static str {
+    match status {
+        UploadStatus::Succeeded => "成功",
+        UploadStatus::Failed => "失败",
+        UploadStatus::Rejected => "校验未通过",
+        UploadStatus::NotUploaded => "未上传",
+    }
+}
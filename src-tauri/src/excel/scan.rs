@@ -0,0 +1,292 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+use walkdir::{DirEntry, WalkDir};
+use crate::commands::types::{ColumnMapping, DuplicateFileGroup, FileEntry, FileFormat, ScanOptions, SheetInfo};
+use super::archive::{self, SEPARATOR};
+use super::cell_to_string;
+
+/// Office 打开文件时生成的锁文件前缀
+const LOCK_FILE_PREFIX: &str = "~$";
+
+/// 扫描时的路径过滤规则
+pub(crate) struct PathFilter {
+    root: std::path::PathBuf,
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    include_hidden: bool,
+}
+
+impl PathFilter {
+    pub fn new(root: &Path, options: &ScanOptions) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let include = if options.include.is_empty() {
+            None
+        } else {
+            Some(build_glob_set(&options.include)?)
+        };
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            include,
+            exclude: build_glob_set(&options.exclude)?,
+            include_hidden: options.include_hidden,
+        })
+    }
+
+    /// 按选项创建目录遍历器，排除的目录不会进入
+    pub fn walk<'a>(&'a self, options: &ScanOptions) -> impl Iterator<Item = walkdir::Result<DirEntry>> + 'a {
+        let mut walker = WalkDir::new(&self.root).follow_links(options.follow_symlinks);
+        if let Some(depth) = options.max_depth {
+            walker = walker.max_depth(depth);
+        }
+        walker.into_iter().filter_entry(move |entry| self.allow_entry(entry))
+    }
+
+    /// 判断文件是否符合 include 规则
+    pub fn allow_file(&self, path: &Path) -> bool {
+        match &self.include {
+            Some(include) => include.is_match(self.relative(path)),
+            None => true,
+        }
+    }
+
+    /// 判断压缩包中的文件是否保留，内部的每一级目录都按锁文件、隐藏和排除规则检查
+    pub fn allow_archive_entry(&self, archive: &str, name: &str) -> bool {
+        let parts: Vec<&str> = name.split('/').filter(|part| !part.is_empty()).collect();
+
+        for (index, part) in parts.iter().enumerate() {
+            if part.starts_with(LOCK_FILE_PREFIX) || (!self.include_hidden && part.starts_with('.')) {
+                return false;
+            }
+            let path = format!("{}{}{}", archive, SEPARATOR, parts[..=index].join("/"));
+            if self.exclude.is_match(self.relative(Path::new(&path))) {
+                tracing::debug!("按排除规则跳过: {}", path);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn allow_entry(&self, entry: &DirEntry) -> bool {
+        // 扫描目录本身不过滤
+        if entry.depth() == 0 {
+            return true;
+        }
+
+        let name = entry.file_name().to_string_lossy();
+        if name.starts_with(LOCK_FILE_PREFIX) {
+            tracing::debug!("跳过锁文件: {}", entry.path().display());
+            return false;
+        }
+        if !self.include_hidden && is_hidden(entry) {
+            tracing::debug!("跳过隐藏的文件或目录: {}", entry.path().display());
+            return false;
+        }
+        if self.exclude.is_match(self.relative(entry.path())) {
+            tracing::debug!("按排除规则跳过: {}", entry.path().display());
+            return false;
+        }
+
+        true
+    }
+
+    /// 相对于扫描目录的路径，统一使用 "/" 分隔
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet, Box<dyn std::error::Error + Send + Sync>> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| format!("无效的匹配规则 {}: {}", pattern, e))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
+
+/// 以 "." 开头的文件或目录视为隐藏，Windows 下还检查隐藏属性
+fn is_hidden(entry: &DirEntry) -> bool {
+    if entry.file_name().to_string_lossy().starts_with('.') {
+        return true;
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if let Ok(metadata) = entry.metadata() {
+            return metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0;
+        }
+    }
+
+    false
+}
+
+/// 创建文件条目，不读取工作簿
+pub(crate) fn file_entry(path: String, size: u64, modified: Option<SystemTime>, format: FileFormat) -> FileEntry {
+    FileEntry {
+        path,
+        size,
+        modified: modified.map(chrono::DateTime::from),
+        format,
+        sheets: None,
+        error: None,
+    }
+}
+
+/// 读取工作簿信息填入文件条目，失败时记录原因；已有错误的条目不再读取
+pub(crate) fn inspect_entry(entry: &mut FileEntry, mappings: &[ColumnMapping]) {
+    if entry.error.is_some() {
+        return;
+    }
+    match inspect_workbook(&entry.path, mappings) {
+        Ok(sheets) => entry.sheets = Some(sheets),
+        Err(e) => {
+            tracing::warn!("读取工作簿信息失败 {}: {}", entry.path, e);
+            entry.error = Some(e.to_string());
+        }
+    }
+}
+
+/// 读取每个工作表的表头和行数，无法读取的工作表记录原因后继续读取其余工作表
+fn inspect_workbook(
+    path: &str,
+    mappings: &[ColumnMapping],
+) -> Result<Vec<SheetInfo>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut workbook = super::format::open_workbook(path)?;
+    let mut sheets = Vec::new();
+
+    for sheet_name in workbook.sheet_names().to_owned() {
+        let range = match workbook.worksheet_range(&sheet_name) {
+            Ok(range) => range,
+            Err(e) => {
+                tracing::warn!("读取工作表失败 {} [{}]: {}", path, sheet_name, e);
+                sheets.push(SheetInfo {
+                    name: sheet_name,
+                    header_row: None,
+                    headers: Vec::new(),
+                    estimated_rows: 0,
+                    headers_match: None,
+                    error: Some(e.to_string()),
+                });
+                continue;
+            }
+        };
+        let headers: Vec<String> = range
+            .rows()
+            .next()
+            .map(|row| row.iter().map(cell_to_string).collect())
+            .unwrap_or_default();
+
+        let headers_match = (!mappings.is_empty()).then(|| {
+            mappings
+                .iter()
+                .all(|m| headers.iter().any(|h| h == &m.original || h == &m.mapped))
+        });
+
+        sheets.push(SheetInfo {
+            header_row: range.start().map(|(row, _)| row as usize + 1),
+            estimated_rows: range.height().saturating_sub(1),
+            headers,
+            headers_match,
+            name: sheet_name,
+            error: None,
+        });
+    }
+
+    Ok(sheets)
+}
+
+/// 找出内容相同的文件，只对大小相同的文件计算哈希
+pub(crate) fn find_duplicate_files(files: &[(String, u64)]) -> io::Result<Vec<DuplicateFileGroup>> {
+    let mut by_size: HashMap<u64, usize> = HashMap::new();
+    for (_, size) in files {
+        *by_size.entry(*size).or_default() += 1;
+    }
+
+    let mut order: Vec<(String, u64)> = Vec::new();
+    let mut by_hash: HashMap<(String, u64), Vec<String>> = HashMap::new();
+
+    for (path, size) in files {
+        if by_size[size] < 2 {
+            continue;
+        }
+
+        let hash = hash_file(path)?;
+        tracing::debug!("文件哈希: {} {}", path, hash);
+
+        let key = (hash, *size);
+        by_hash
+            .entry(key.clone())
+            .or_insert_with(|| {
+                order.push(key);
+                Vec::new()
+            })
+            .push(path.clone());
+    }
+
+    let groups = order
+        .into_iter()
+        .filter_map(|key| {
+            let files = by_hash.remove(&key)?;
+            (files.len() > 1).then_some(DuplicateFileGroup {
+                hash: key.0,
+                size: key.1,
+                files,
+            })
+        })
+        .collect();
+
+    Ok(groups)
+}
+
+/// 计算文件内容的 SHA-256
+fn hash_file(path: &str) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    if archive::virtual_parts(path).is_some() {
+        hasher.update(archive::read_input(path)?);
+    } else {
+        io::copy(&mut File::open(path)?, &mut hasher)?;
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::excel::testing::{temp_path, write_workbook};
+
+    #[test]
+    fn unreadable_sheet_keeps_other_sheets() {
+        let path = temp_path("xlsx");
+        write_workbook(&path, &[("数据", &[&["申请号", "名称"], &["CN201310123403.0", "a"]])], &["损坏"]);
+        let mappings = [ColumnMapping { original: "名称".to_string(), mapped: "专利名称".to_string() }];
+
+        let result = inspect_workbook(&path.to_string_lossy(), &mappings);
+        std::fs::remove_file(&path).unwrap();
+        let sheets = result.unwrap();
+
+        assert_eq!(sheets.len(), 2);
+        assert_eq!(sheets[0].name, "数据");
+        assert_eq!(sheets[0].estimated_rows, 1);
+        assert_eq!(sheets[0].headers_match, Some(true));
+        assert!(sheets[0].error.is_none());
+        assert_eq!(sheets[1].name, "损坏");
+        assert!(sheets[1].headers.is_empty());
+        assert!(sheets[1].error.is_some());
+    }
+}
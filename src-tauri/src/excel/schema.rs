@@ -0,0 +1,233 @@
+use std::collections::{HashMap, HashSet};
+use crate::cli::ImportMode;
+use crate::commands::types::{
+    ColumnMapping, ColumnRename, ComputedValue, ConvertConfig, SchemaCheck, ValidationSchema,
+};
+use super::computed::rename_template_column;
+
+/// 读取目标列定义文件，并检查列名和别名是否冲突
+pub fn load_schema(path: &str) -> Result<ValidationSchema, Box<dyn std::error::Error + Send + Sync>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取目标列定义文件失败 {}: {}", path, e))?;
+    let schema: ValidationSchema = serde_json::from_str(&content)
+        .map_err(|e| format!("解析目标列定义文件失败 {}: {}", path, e))?;
+    check_schema(&schema).map_err(|e| format!("目标列定义无效 {}: {}", path, e))?;
+    tracing::info!("读取目标列定义: {} (版本 {}, {} 列)", path, schema.version, schema.columns.len());
+    Ok(schema)
+}
+
+/// 同一列表中的列名不能重复，别名不能与其他列的列名或别名相同
+///
+/// 按导入模式追加的规则可以与通用规则同名，用于在该模式下收紧校验。
+pub fn check_schema(schema: &ValidationSchema) -> Result<(), String> {
+    for rules in std::iter::once(&schema.columns).chain(schema.modes.values()) {
+        let mut names = HashSet::new();
+        for rule in rules {
+            if !names.insert(&rule.name) {
+                return Err(format!("列重复定义: {}", rule.name));
+            }
+        }
+    }
+
+    let names: HashSet<&str> = schema.all_rules().map(|rule| rule.name.as_str()).collect();
+    let mut aliases: HashMap<&str, &str> = HashMap::new();
+    for rule in schema.all_rules() {
+        for alias in &rule.aliases {
+            if alias != &rule.name && names.contains(alias.as_str()) {
+                return Err(format!("列 {} 的别名 {} 与其他列同名", rule.name, alias));
+            }
+            match aliases.insert(alias, &rule.name) {
+                Some(other) if other != rule.name => {
+                    return Err(format!("别名 {} 同时属于列 {} 和 {}", alias, other, rule.name));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut versions = HashSet::new();
+    for migration in &schema.migrations {
+        if migration.version > schema.version {
+            return Err(format!("列名变更的版本 {} 大于定义的版本 {}", migration.version, schema.version));
+        }
+        if !versions.insert(migration.version) {
+            return Err(format!("列名变更的版本重复: {}", migration.version));
+        }
+    }
+
+    Ok(())
+}
+
+/// 检查列映射：映射到未定义的列、多个源列映射到同一列
+pub fn check_mappings(schema: &ValidationSchema, mappings: &[ColumnMapping]) -> Vec<String> {
+    let names: HashSet<&str> = schema.all_rules().map(|rule| rule.name.as_str()).collect();
+    let mut issues = Vec::new();
+    let mut sources: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for mapping in mappings {
+        if !names.contains(mapping.mapped.as_str()) {
+            issues.push(format!("列映射 {} -> {} 的目标列未在目标列定义中", mapping.original, mapping.mapped));
+        }
+        let originals = sources.entry(&mapping.mapped).or_default();
+        if !originals.contains(&mapping.original.as_str()) {
+            originals.push(&mapping.original);
+        }
+    }
+
+    // 同一文件中通常只会出现其中一个源列，这里只提示
+    for mapping in mappings {
+        if let Some(originals) = sources.remove(mapping.mapped.as_str()) {
+            if originals.len() > 1 {
+                issues.push(format!("多个源列映射到 {}: {}", mapping.mapped, originals.join(", ")));
+            }
+        }
+    }
+
+    issues
+}
+
+/// 将转换配置中所有引用列 `rename.from` 的地方改为 `rename.to`，返回是否有改动
+fn migrate_column(config: &mut ConvertConfig, rename: &ColumnRename) -> bool {
+    let mut applied = false;
+
+    let filter_columns = config.import_filter.iter_mut().flat_map(|filter| {
+        [&mut filter.application_number_column, &mut filter.legal_status_column]
+            .into_iter()
+            .chain(filter.type_column.as_mut())
+    });
+    let columns = config
+        .mappings
+        .iter_mut()
+        .chain(config.scan.mappings.iter_mut())
+        .map(|mapping| &mut mapping.mapped)
+        .chain(config.sheets.required_columns.iter_mut())
+        .chain(config.transforms.iter_mut().map(|transform| &mut transform.column))
+        .chain(config.required_columns.iter_mut())
+        .chain(config.computed_columns.iter_mut().map(|column| &mut column.name))
+        .chain(filter_columns)
+        .chain(config.duplicates.iter_mut().flat_map(|duplicates| duplicates.key_columns.iter_mut()));
+    for column in columns.filter(|column| **column == rename.from) {
+        *column = rename.to.clone();
+        applied = true;
+    }
+
+    for column in &mut config.computed_columns {
+        if let ComputedValue::Template { template } = &mut column.value {
+            if let Some(renamed) = rename_template_column(template, &rename.from, &rename.to) {
+                *template = renamed;
+                applied = true;
+            }
+        }
+    }
+
+    applied
+}
+
+/// 按目标列定义更新转换配置
+///
+/// 依次迁移按旧版本编写的列名（行处理脚本中的列名只检查并提示）、检查列映射、为别名添加列映射（已有映射的源列不覆盖），
+/// 并将该导入模式下的必需列加入 `required_columns`。
+pub fn apply_schema(schema: &ValidationSchema, config: &mut ConvertConfig, mode: ImportMode) -> SchemaCheck {
+    let mut check = SchemaCheck::default();
+
+    let from_version = config.schema_version.unwrap_or(0);
+    let mut migrations: Vec<_> = schema
+        .migrations
+        .iter()
+        .filter(|migration| migration.version > from_version)
+        .collect();
+    migrations.sort_by_key(|migration| migration.version);
+    let renames: Vec<&ColumnRename> = migrations.into_iter().flat_map(|migration| &migration.renames).collect();
+    for rename in &renames {
+        if migrate_column(config, rename) {
+            tracing::info!("迁移列名: {} -> {}", rename.from, rename.to);
+            check.migrated.push((*rename).clone());
+        }
+    }
+    if let Some(script) = &config.script {
+        // 脚本中的列名无法自动迁移，只提示需要手动修改
+        match std::fs::read_to_string(&script.path) {
+            Ok(source) => {
+                for rename in renames.iter().filter(|rename| source.contains(rename.from.as_str())) {
+                    check.issues.push(format!(
+                        "行处理脚本 {} 中可能引用了旧列名 {}，需要手动改为 {}",
+                        script.path, rename.from, rename.to
+                    ));
+                }
+            }
+            Err(e) if !renames.is_empty() => {
+                check.issues.push(format!("无法读取行处理脚本 {}，其中的列名未迁移: {}", script.path, e));
+            }
+            Err(_) => {}
+        }
+    }
+    if config.schema_version.is_some_and(|version| version > schema.version) {
+        check.issues.push(format!(
+            "转换配置的版本 {} 高于目标列定义的版本 {}",
+            config.schema_version.unwrap_or_default(),
+            schema.version
+        ));
+    }
+    config.schema_version = Some(schema.version);
+
+    check.issues.extend(check_mappings(schema, &config.mappings));
+    for issue in &check.issues {
+        tracing::warn!("{}", issue);
+    }
+
+    let mut originals: HashSet<String> = config.mappings.iter().map(|m| m.original.clone()).collect();
+    for rule in schema.all_rules() {
+        for alias in &rule.aliases {
+            if alias != &rule.name && originals.insert(alias.clone()) {
+                check.alias_mappings.push(ColumnMapping {
+                    original: alias.clone(),
+                    mapped: rule.name.clone(),
+                });
+            }
+        }
+    }
+    config.mappings.extend(check.alias_mappings.iter().cloned());
+
+    for rule in schema.rules_for(mode).into_iter().filter(|rule| rule.required) {
+        if !config.required_columns.contains(&rule.name) {
+            config.required_columns.push(rule.name);
+        }
+    }
+
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> ConvertConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn migrate_column_renames_every_reference() {
+        let mut config = parse(
+            r#"{
+                "mappings": [{ "original": "标题", "mapped": "发明名称" }],
+                "transforms": [{ "column": "发明名称", "steps": [] }],
+                "required_columns": ["发明名称"],
+                "sheets": { "mode": "required_columns", "required_columns": ["申请号", "发明名称"] },
+                "scan": { "mappings": [{ "original": "名称", "mapped": "发明名称" }] },
+                "duplicates": { "key_columns": ["发明名称"] }
+            }"#,
+        );
+        let rename = ColumnRename { from: "发明名称".into(), to: "专利名称".into() };
+
+        assert!(migrate_column(&mut config, &rename));
+        assert_eq!(config.mappings[0].mapped, "专利名称");
+        assert_eq!(config.transforms[0].column, "专利名称");
+        assert_eq!(config.required_columns, ["专利名称"]);
+        assert_eq!(config.sheets.required_columns, ["申请号", "专利名称"]);
+        assert_eq!(config.scan.mappings[0].mapped, "专利名称");
+        assert_eq!(config.duplicates.unwrap().key_columns, ["专利名称"]);
+        // 没有引用旧列名时不算迁移
+        let mut unrelated = parse(r#"{ "sheets": { "required_columns": ["申请号"] } }"#);
+        assert!(!migrate_column(&mut unrelated, &rename));
+    }
+}
@@ -0,0 +1,113 @@
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{CallFnOptions, Dynamic, Engine, Map, Scope, AST};
+use crate::commands::types::{ScriptConfig, ScriptErrorAction};
+
+/// 脚本入口函数名
+const ENTRY_FN: &str = "transform";
+
+/// 单行脚本允许执行的最大操作数，防止死循环
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// 脚本中 `skip()` 返回的跳过信号
+#[derive(Debug, Clone)]
+struct SkipRow;
+
+/// 行处理脚本
+pub(crate) struct RowScript {
+    engine: Engine,
+    ast: AST,
+    on_error: ScriptErrorAction,
+}
+
+impl RowScript {
+    pub fn load(config: &ScriptConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        tracing::info!("加载行处理脚本: {}", config.path);
+
+        let source = std::fs::read_to_string(&config.path)
+            .map_err(|e| format!("读取脚本文件失败 {}: {}", config.path, e))?;
+
+        let engine = create_engine();
+        let ast = engine
+            .compile(&source)
+            .map_err(|e| format!("脚本编译失败 {}: {}", config.path, e))?;
+
+        if !ast.iter_functions().any(|f| f.name == ENTRY_FN && f.params.len() == 1) {
+            return Err(format!("脚本 {} 中未定义 fn {}(row)", config.path, ENTRY_FN).into());
+        }
+
+        Ok(Self {
+            engine,
+            ast,
+            on_error: config.on_error,
+        })
+    }
+
+    pub fn on_error(&self) -> ScriptErrorAction {
+        self.on_error
+    }
+
+    /// 对一行数据执行脚本，返回 `Ok(false)` 表示跳过该行
+    ///
+    /// 脚本返回 map 时按列名写回；返回 `()` 时保持不变；返回 `skip()` 时跳过该行。
+    pub fn run(&self, headers: &[String], row: &mut [String]) -> Result<bool, String> {
+        let input: Map = headers
+            .iter()
+            .zip(row.iter())
+            .map(|(header, value)| (header.as_str().into(), Dynamic::from(value.clone())))
+            .collect();
+
+        let options = CallFnOptions::new().eval_ast(false);
+        let output: Dynamic = self
+            .engine
+            .call_fn_with_options(options, &mut Scope::new(), &self.ast, ENTRY_FN, (input,))
+            .map_err(|e| e.to_string())?;
+
+        if output.is::<SkipRow>() {
+            return Ok(false);
+        }
+        if output.is_unit() {
+            return Ok(true);
+        }
+
+        let type_name = output.type_name();
+        let output = output
+            .try_cast::<Map>()
+            .ok_or_else(|| format!("{} 应返回 map、() 或 skip()，实际返回 {}", ENTRY_FN, type_name))?;
+
+        for (header, value) in headers.iter().zip(row.iter_mut()) {
+            if let Some(new_value) = output.get(header.as_str()) {
+                *value = if new_value.is_unit() {
+                    String::new()
+                } else {
+                    new_value.to_string()
+                };
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// 创建受限的脚本引擎：禁止加载模块和 eval，并限制资源占用
+fn create_engine() -> Engine {
+    let mut engine = Engine::new();
+
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(64 * 1024)
+        .set_max_array_size(10_000)
+        .set_max_map_size(10_000)
+        .disable_symbol("eval");
+
+    engine
+        .register_type_with_name::<SkipRow>("SkipRow")
+        .register_fn("skip", || SkipRow);
+
+    engine.on_print(|text| tracing::info!("[脚本] {}", text));
+    engine.on_debug(|text, _, pos| tracing::debug!("[脚本] {:?} {}", pos, text));
+
+    engine
+}
@@ -0,0 +1,92 @@
+use regex::Regex;
+use crate::commands::types::{NamePattern, SheetSelection, SheetSelectionMode};
+
+/// 编译后的名称匹配规则
+enum NameMatcher {
+    Name(String),
+    Regex(Regex),
+}
+
+impl NameMatcher {
+    fn new(pattern: &NamePattern) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        match pattern {
+            NamePattern::Name(name) => Ok(NameMatcher::Name(name.clone())),
+            NamePattern::Regex(expr) => Regex::new(expr)
+                .map(NameMatcher::Regex)
+                .map_err(|e| format!("工作表正则表达式无效 '{}': {}", expr, e).into()),
+        }
+    }
+
+    fn is_match(&self, name: &str) -> bool {
+        match self {
+            NameMatcher::Name(expected) => expected == name,
+            NameMatcher::Regex(regex) => regex.is_match(name),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            NameMatcher::Name(name) => format!("名称 '{}'", name),
+            NameMatcher::Regex(regex) => format!("正则 '{}'", regex.as_str()),
+        }
+    }
+}
+
+/// 工作表过滤器
+pub(crate) struct SheetFilter {
+    mode: SheetSelectionMode,
+    include: Vec<NameMatcher>,
+    exclude: Vec<NameMatcher>,
+    required_columns: Vec<String>,
+}
+
+impl SheetFilter {
+    pub fn new(selection: &SheetSelection) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        if selection.mode == SheetSelectionMode::RequiredColumns && selection.required_columns.is_empty() {
+            return Err("工作表选择模式为 required_columns 时必须配置 required_columns".into());
+        }
+
+        Ok(Self {
+            mode: selection.mode,
+            include: selection.include.iter().map(NameMatcher::new).collect::<Result<_, _>>()?,
+            exclude: selection.exclude.iter().map(NameMatcher::new).collect::<Result<_, _>>()?,
+            required_columns: selection.required_columns.clone(),
+        })
+    }
+
+    /// 按工作表序号和名称检查，返回跳过原因
+    pub fn check_name(&self, index: usize, name: &str) -> Option<String> {
+        if self.mode == SheetSelectionMode::FirstOnly && index > 0 {
+            return Some("仅转换第一个工作表".to_string());
+        }
+
+        if !self.include.is_empty() && !self.include.iter().any(|m| m.is_match(name)) {
+            return Some("不在包含列表中".to_string());
+        }
+
+        self.exclude
+            .iter()
+            .find(|m| m.is_match(name))
+            .map(|m| format!("匹配排除规则: {}", m.describe()))
+    }
+
+    /// 检查映射后的表头是否包含必需列，返回跳过原因
+    pub fn check_headers(&self, headers: &[String]) -> Option<String> {
+        if self.mode != SheetSelectionMode::RequiredColumns {
+            return None;
+        }
+
+        let missing: Vec<&str> = self
+            .required_columns
+            .iter()
+            .filter(|column| !headers.iter().any(|h| h == *column))
+            .map(|column| column.as_str())
+            .collect();
+
+        if missing.is_empty() {
+            None
+        } else {
+            Some(format!("缺少必需列: {}", missing.join(", ")))
+        }
+    }
+}
@@ -0,0 +1,455 @@
+use pinyin::ToPinyin;
+use std::collections::{HashMap, HashSet};
+use crate::commands::types::{
+    ColumnMapping, FailedSheet, HeaderFrequency, MappingSuggestion, MappingSuggestions, MatchKind, RejectedFile, ValidationSchema,
+};
+use super::{cell_to_string, format};
+
+/// 默认的最低相似度，低于该值的候选不返回
+pub const DEFAULT_MIN_SCORE: f64 = 0.6;
+
+/// 检查最低相似度是否在 0 到 1 之间
+pub fn check_min_score(min_score: f64) -> Result<(), String> {
+    if (0.0..=1.0).contains(&min_score) {
+        Ok(())
+    } else {
+        Err(format!("最低相似度必须在 0 到 1 之间: {}", min_score))
+    }
+}
+
+/// 每个表头最多保留的候选数
+const MAX_CANDIDATES: usize = 3;
+
+/// 各匹配方式的相似度，模糊匹配按相似程度乘以 `FUZZY_WEIGHT`
+const ALIAS_SCORE: f64 = 0.98;
+const NORMALIZED_SCORE: f64 = 0.95;
+const PINYIN_SCORE: f64 = 0.9;
+const INITIALS_SCORE: f64 = 0.8;
+const FUZZY_WEIGHT: f64 = 0.85;
+
+/// 用于比较的列名形式
+struct HeaderKeys<'a> {
+    text: &'a str,
+    /// 全角转半角、小写，去掉空白和标点
+    normalized: Vec<char>,
+    /// 每个汉字替换为不带声调的拼音，其他字符保持不变
+    syllables: Vec<String>,
+    /// 拼音首字母
+    initials: String,
+    has_chinese: bool,
+}
+
+impl<'a> HeaderKeys<'a> {
+    fn new(text: &'a str) -> Self {
+        let normalized: Vec<char> = text
+            .chars()
+            .map(to_half_width)
+            .filter(|c| !is_separator(*c))
+            .flat_map(char::to_lowercase)
+            .collect();
+
+        let mut has_chinese = false;
+        let syllables: Vec<String> = normalized
+            .iter()
+            .map(|c| match c.to_pinyin() {
+                Some(pinyin) => {
+                    has_chinese = true;
+                    pinyin.plain().to_string()
+                }
+                None => c.to_string(),
+            })
+            .collect();
+        let initials = syllables.iter().filter_map(|s| s.chars().next()).collect();
+
+        Self {
+            text,
+            normalized,
+            syllables,
+            initials,
+            has_chinese,
+        }
+    }
+
+    /// 与目标列名的相似度和匹配方式，完全不相似时返回 None
+    fn compare(&self, target: &HeaderKeys) -> Option<(f64, MatchKind)> {
+        if self.normalized.is_empty() || target.normalized.is_empty() {
+            return None;
+        }
+        if self.text == target.text {
+            return Some((1.0, MatchKind::Exact));
+        }
+        if self.normalized == target.normalized {
+            return Some((NORMALIZED_SCORE, MatchKind::Normalized));
+        }
+        if self.has_chinese || target.has_chinese {
+            if self.syllables.concat() == target.syllables.concat() {
+                return Some((PINYIN_SCORE, MatchKind::Pinyin));
+            }
+            // 一方为汉字、另一方为拼音首字母缩写，例如 "申请号" 与 "SQH"
+            let abbreviation = |chinese: &HeaderKeys, other: &HeaderKeys| {
+                chinese.has_chinese
+                    && !other.has_chinese
+                    && other.normalized.len() >= 2
+                    && other.normalized.iter().copied().eq(chinese.initials.chars())
+            };
+            if abbreviation(self, target) || abbreviation(target, self) {
+                return Some((INITIALS_SCORE, MatchKind::Pinyin));
+            }
+        }
+
+        let similarity = similarity(&self.normalized, &target.normalized)
+            .max(similarity(&self.syllables, &target.syllables))
+            .max(containment(&self.normalized, &target.normalized));
+        (similarity > 0.0).then_some((similarity * FUZZY_WEIGHT, MatchKind::Fuzzy))
+    }
+}
+
+/// 目标列及其别名
+struct Target<'a> {
+    name: HeaderKeys<'a>,
+    aliases: Vec<HeaderKeys<'a>>,
+}
+
+impl Target<'_> {
+    /// 与列名和各别名比较，取相似度最高的结果
+    fn compare(&self, header: &HeaderKeys) -> Option<(f64, MatchKind)> {
+        let aliases = self.aliases.iter().filter_map(|alias| {
+            header.compare(alias).map(|(score, kind)| match kind {
+                MatchKind::Exact => (ALIAS_SCORE, MatchKind::Alias),
+                _ => (score, kind),
+            })
+        });
+        header
+            .compare(&self.name)
+            .into_iter()
+            .chain(aliases)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+    }
+}
+
+/// 收集文件中所有不同的表头，并为每个表头给出最相似的目标列
+///
+/// 目标列为 `targets` 和目标列定义中的所有列，定义中的别名也参与比较。
+pub(crate) fn suggest_mappings(
+    files: &[String],
+    targets: &[String],
+    schema: Option<&ValidationSchema>,
+    min_score: f64,
+) -> MappingSuggestions {
+    let (headers, failed_files, failed_sheets) = collect_headers(files);
+
+    let mut names: Vec<(&str, Vec<&str>)> = Vec::new();
+    let columns = targets
+        .iter()
+        .map(|target| (target.as_str(), &[][..]))
+        .chain(schema.into_iter().flat_map(|schema| {
+            schema.all_rules().map(|rule| (rule.name.as_str(), rule.aliases.as_slice()))
+        }));
+    for (name, aliases) in columns {
+        if name.trim().is_empty() {
+            continue;
+        }
+        let index = match names.iter().position(|(existing, _)| *existing == name) {
+            Some(index) => index,
+            None => {
+                names.push((name, Vec::new()));
+                names.len() - 1
+            }
+        };
+        for alias in aliases {
+            if !names[index].1.contains(&alias.as_str()) {
+                names[index].1.push(alias);
+            }
+        }
+    }
+    let targets: Vec<Target> = names
+        .into_iter()
+        .map(|(name, aliases)| Target {
+            name: HeaderKeys::new(name),
+            aliases: aliases.into_iter().map(HeaderKeys::new).collect(),
+        })
+        .collect();
+
+    let mut suggestions = Vec::new();
+    let mut unmatched = Vec::new();
+    for header in &headers {
+        let keys = HeaderKeys::new(&header.header);
+        let mut candidates: Vec<MappingSuggestion> = targets
+            .iter()
+            .filter_map(|target| {
+                let (score, match_kind) = target.compare(&keys)?;
+                (score >= min_score).then(|| MappingSuggestion {
+                    original: header.header.clone(),
+                    mapped: target.name.text.to_string(),
+                    score,
+                    match_kind,
+                    frequency: header.sheets,
+                })
+            })
+            .collect();
+        if candidates.is_empty() {
+            unmatched.push(header.header.clone());
+            continue;
+        }
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.mapped.cmp(&b.mapped)));
+        candidates.truncate(MAX_CANDIDATES);
+        suggestions.extend(candidates);
+    }
+
+    suggestions.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| b.frequency.cmp(&a.frequency))
+            .then_with(|| a.original.cmp(&b.original))
+    });
+    tracing::info!(
+        "共 {} 个不同的表头，{} 条映射建议，{} 个表头没有候选",
+        headers.len(),
+        suggestions.len(),
+        unmatched.len()
+    );
+
+    MappingSuggestions {
+        headers,
+        suggestions,
+        unmatched,
+        failed_files,
+        failed_sheets,
+    }
+}
+
+/// 从建议中为每个表头选出一个目标列，每个目标列只使用一次
+///
+/// 按建议的顺序（相似度从高到低）依次选取；表头与目标列相同时无需映射，只占用该目标列。
+pub fn best_mappings(suggestions: &[MappingSuggestion]) -> Vec<ColumnMapping> {
+    let mut used_headers = HashSet::new();
+    let mut used_targets = HashSet::new();
+    let mut mappings = Vec::new();
+
+    for suggestion in suggestions {
+        if used_headers.contains(&suggestion.original) || used_targets.contains(&suggestion.mapped) {
+            continue;
+        }
+        used_headers.insert(suggestion.original.clone());
+        used_targets.insert(suggestion.mapped.clone());
+        if suggestion.original != suggestion.mapped {
+            mappings.push(ColumnMapping {
+                original: suggestion.original.clone(),
+                mapped: suggestion.mapped.clone(),
+            });
+        }
+    }
+
+    mappings
+}
+
+/// 读取每个工作表的首行，统计各表头出现的工作表数和文件数
+fn collect_headers(files: &[String]) -> (Vec<HeaderFrequency>, Vec<RejectedFile>, Vec<FailedSheet>) {
+    let mut headers: Vec<HeaderFrequency> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut failed_files = Vec::new();
+    let mut failed_sheets = Vec::new();
+
+    for file in files {
+        let sheets = match read_headers(file, &mut failed_sheets) {
+            Ok(sheets) => sheets,
+            Err(e) => {
+                tracing::warn!("读取表头失败 {}: {}", file, e);
+                failed_files.push(RejectedFile {
+                    path: file.clone(),
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let mut seen_in_file = HashSet::new();
+        for sheet_headers in sheets {
+            let mut seen_in_sheet = HashSet::new();
+            for header in sheet_headers {
+                if header.trim().is_empty() || !seen_in_sheet.insert(header.clone()) {
+                    continue;
+                }
+                let index = *positions.entry(header.clone()).or_insert_with(|| {
+                    headers.push(HeaderFrequency {
+                        header: header.clone(),
+                        sheets: 0,
+                        files: 0,
+                    });
+                    headers.len() - 1
+                });
+                headers[index].sheets += 1;
+                if seen_in_file.insert(header) {
+                    headers[index].files += 1;
+                }
+            }
+        }
+    }
+
+    headers.sort_by(|a, b| b.sheets.cmp(&a.sheets).then_with(|| b.files.cmp(&a.files)));
+    (headers, failed_files, failed_sheets)
+}
+
+/// 读取文件中每个工作表的首行，无法读取的工作表记入 `failed_sheets` 后跳过
+fn read_headers(
+    path: &str,
+    failed_sheets: &mut Vec<FailedSheet>,
+) -> Result<Vec<Vec<String>>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut workbook = format::open_workbook(path)?;
+    let mut sheets = Vec::new();
+    for sheet_name in workbook.sheet_names().to_owned() {
+        let range = match workbook.worksheet_range(&sheet_name) {
+            Ok(range) => range,
+            Err(e) => {
+                tracing::warn!("读取工作表失败 {} [{}]: {}", path, sheet_name, e);
+                failed_sheets.push(FailedSheet {
+                    file: path.to_string(),
+                    sheet: sheet_name,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+        if let Some(row) = range.rows().next() {
+            sheets.push(row.iter().map(cell_to_string).collect());
+        }
+    }
+    Ok(sheets)
+}
+
+/// 编辑距离换算的相似度，0 到 1
+fn similarity<T: PartialEq>(a: &[T], b: &[T]) -> f64 {
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 0.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
+
+fn levenshtein<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != y);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// 一方包含另一方时（例如 "专利申请号" 与 "申请号"）的相似度，按长度比例给分
+fn containment(a: &[char], b: &[char]) -> f64 {
+    let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if shorter.len() < 2 || !longer.windows(shorter.len()).any(|window| window == shorter) {
+        return 0.0;
+    }
+    0.6 + 0.4 * shorter.len() as f64 / longer.len() as f64
+}
+
+/// 全角字符转为半角
+fn to_half_width(c: char) -> char {
+    match c {
+        '\u{3000}' => ' ',
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        _ => c,
+    }
+}
+
+/// 比较列名时忽略的空白和标点
+fn is_separator(c: char) -> bool {
+    c.is_whitespace()
+        || c.is_ascii_punctuation()
+        || matches!(c, '、' | '。' | '·' | '【' | '】' | '《' | '》' | '“' | '”' | '‘' | '’' | '〔' | '〕')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compare(header: &str, target: &str) -> Option<(f64, MatchKind)> {
+        HeaderKeys::new(header).compare(&HeaderKeys::new(target))
+    }
+
+    fn suggestion(original: &str, mapped: &str, score: f64) -> MappingSuggestion {
+        MappingSuggestion {
+            original: original.to_string(),
+            mapped: mapped.to_string(),
+            score,
+            match_kind: MatchKind::Fuzzy,
+            frequency: 1,
+        }
+    }
+
+    #[test]
+    fn normalizes_width_case_and_punctuation() {
+        assert_eq!(compare("申请日", "申请日"), Some((1.0, MatchKind::Exact)));
+        assert_eq!(compare("申请 日", "申请日"), Some((NORMALIZED_SCORE, MatchKind::Normalized)));
+        assert_eq!(compare("ＩＰＣ分类号", "IPC分类号"), Some((NORMALIZED_SCORE, MatchKind::Normalized)));
+        assert_eq!(compare("【申请人】", "申请人"), Some((NORMALIZED_SCORE, MatchKind::Normalized)));
+        assert_eq!(compare("  ", "申请人"), None);
+    }
+
+    #[test]
+    fn matches_pinyin_and_initials() {
+        assert_eq!(compare("shenqingren", "申请人"), Some((PINYIN_SCORE, MatchKind::Pinyin)));
+        assert_eq!(compare("Shen Qing Ren", "申请人"), Some((PINYIN_SCORE, MatchKind::Pinyin)));
+        assert_eq!(compare("SQR", "申请人"), Some((INITIALS_SCORE, MatchKind::Pinyin)));
+        assert_eq!(compare("申请人", "sqr"), Some((INITIALS_SCORE, MatchKind::Pinyin)));
+        // 单个字母不作为缩写
+        assert_ne!(compare("S", "申"), Some((INITIALS_SCORE, MatchKind::Pinyin)));
+    }
+
+    #[test]
+    fn fuzzy_matches_contained_names() {
+        let (score, kind) = compare("专利申请号", "申请号").unwrap();
+        assert_eq!(kind, MatchKind::Fuzzy);
+        assert!((DEFAULT_MIN_SCORE..INITIALS_SCORE).contains(&score));
+        assert!(!matches!(compare("发明人", "公开日"), Some((score, _)) if score >= DEFAULT_MIN_SCORE));
+    }
+
+    #[test]
+    fn aliases_score_below_exact_names() {
+        let target = Target {
+            name: HeaderKeys::new("申请号"),
+            aliases: vec![HeaderKeys::new("专利号")],
+        };
+        assert_eq!(target.compare(&HeaderKeys::new("专利号")), Some((ALIAS_SCORE, MatchKind::Alias)));
+        assert_eq!(target.compare(&HeaderKeys::new("申请号")), Some((1.0, MatchKind::Exact)));
+    }
+
+    #[test]
+    fn best_mappings_use_each_header_and_target_once() {
+        let suggestions = [
+            suggestion("申请号", "申请号", 1.0),
+            suggestion("专利申请号", "申请号", 0.9),
+            suggestion("专利申请号", "公开号", 0.7),
+            suggestion("SQR", "申请人", 0.8),
+            suggestion("SQR", "申请日", 0.6),
+        ];
+        let mappings: Vec<(String, String)> = best_mappings(&suggestions)
+            .into_iter()
+            .map(|mapping| (mapping.original, mapping.mapped))
+            .collect();
+
+        assert_eq!(
+            mappings,
+            vec![
+                ("专利申请号".to_string(), "公开号".to_string()),
+                ("SQR".to_string(), "申请人".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn min_score_must_be_between_zero_and_one() {
+        assert!(check_min_score(0.0).is_ok());
+        assert!(check_min_score(DEFAULT_MIN_SCORE).is_ok());
+        assert!(check_min_score(1.0).is_ok());
+        assert!(check_min_score(-0.1).is_err());
+        assert!(check_min_score(1.5).is_err());
+        assert!(check_min_score(f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,80 @@
+//! 单元测试使用的工作簿
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 临时目录下不重复的文件路径
+pub(crate) fn temp_path(extension: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("patent-test-{}.{}", uuid::Uuid::new_v4(), extension))
+}
+
+/// 写出一个最小的 xlsx 工作簿，可以解析为数字的值写为数值单元格
+///
+/// `missing` 中的工作表只在 workbook.xml 中声明，没有对应的工作表文件，读取时会出错。
+pub(crate) fn write_workbook(path: &Path, sheets: &[(&str, &[&[&str]])], missing: &[&str]) {
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    let options = zip::write::FileOptions::default();
+    let names: Vec<&str> = sheets.iter().map(|(name, _)| *name).chain(missing.iter().copied()).collect();
+
+    let mut content_types = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#,
+    );
+    let mut workbook = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>"#,
+    );
+    let mut rels = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    );
+    for (index, name) in names.iter().enumerate() {
+        let id = index + 1;
+        content_types.push_str(&format!(
+            r#"<Override PartName="/xl/worksheets/sheet{id}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#
+        ));
+        workbook.push_str(&format!(r#"<sheet name="{name}" sheetId="{id}" r:id="rId{id}"/>"#));
+        rels.push_str(&format!(
+            r#"<Relationship Id="rId{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{id}.xml"/>"#
+        ));
+    }
+    content_types.push_str("</Types>");
+    workbook.push_str("</sheets></workbook>");
+    rels.push_str("</Relationships>");
+
+    let files = [
+        ("[Content_Types].xml", content_types),
+        (
+            "_rels/.rels",
+            r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#.to_string(),
+        ),
+        ("xl/workbook.xml", workbook),
+        ("xl/_rels/workbook.xml.rels", rels),
+    ];
+    for (name, content) in files {
+        zip.start_file(name, options).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+
+    for (index, (_, rows)) in sheets.iter().enumerate() {
+        let mut data = String::new();
+        for (row, values) in rows.iter().enumerate() {
+            data.push_str(&format!(r#"<row r="{}">"#, row + 1));
+            for (column, value) in values.iter().enumerate() {
+                let reference = format!("{}{}", char::from(b'A' + column as u8), row + 1);
+                if value.parse::<f64>().is_ok() {
+                    data.push_str(&format!(r#"<c r="{reference}"><v>{value}</v></c>"#));
+                } else {
+                    data.push_str(&format!(r#"<c r="{reference}" t="inlineStr"><is><t>{value}</t></is></c>"#));
+                }
+            }
+            data.push_str("</row>");
+        }
+        zip.start_file(format!("xl/worksheets/sheet{}.xml", index + 1), options).unwrap();
+        zip.write_all(
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>{data}</sheetData></worksheet>"#
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    }
+    zip.finish().unwrap();
+}
@@ -0,0 +1,54 @@
+use calamine::{Data, Range};
+use encoding_rs::{Encoding, GB18030};
+use std::borrow::Cow;
+
+/// 解码文本文件：优先按 BOM 判断，其次尝试 UTF-8，否则按 GB18030（兼容 GBK）解码
+pub(crate) fn decode(bytes: &[u8]) -> (Cow<'_, str>, &'static str) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return (text, encoding.name());
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (Cow::Borrowed(text), "UTF-8"),
+        Err(_) => {
+            let (text, had_errors) = GB18030.decode_without_bom_handling(bytes);
+            if had_errors {
+                tracing::warn!("文件内容不是有效的 UTF-8 或 GBK/GB18030 编码，部分字符无法识别");
+            }
+            (text, "GB18030")
+        }
+    }
+}
+
+/// 将 CSV/TSV 内容读取为单元格区域，所有单元格均为文本
+pub(crate) fn read_table(bytes: &[u8], delimiter: u8) -> Result<Range<Data>, Box<dyn std::error::Error + Send + Sync>> {
+    let (text, encoding) = decode(bytes);
+    tracing::debug!("文本文件编码: {}", encoding);
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(text.as_bytes());
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        rows.push(record.iter().map(|value| value.to_string()).collect::<Vec<_>>());
+    }
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    if rows.is_empty() || width == 0 {
+        return Ok(Range::empty());
+    }
+
+    let mut range = Range::new((0, 0), (rows.len() as u32 - 1, width as u32 - 1));
+    for (row_index, row) in rows.into_iter().enumerate() {
+        for (col_index, value) in row.into_iter().enumerate() {
+            range.set_value((row_index as u32, col_index as u32), Data::String(value));
+        }
+    }
+
+    Ok(range)
+}
@@ -0,0 +1,225 @@
+use chrono::{Duration, NaiveDate};
+use std::collections::HashMap;
+use crate::commands::types::{ColumnTransform, Transform};
+
+/// Excel 日期序列号的起始日期（已包含 1900 年闰年问题的修正）
+const EXCEL_EPOCH: (i32, u32, u32) = (1899, 12, 30);
+
+/// 按 Excel 日期序列号解析的取值范围（1954-10-03 至 2119-01-10），
+/// 范围外的数字（如年份 2023）不当作日期
+const EXCEL_SERIAL_RANGE: std::ops::RangeInclusive<f64> = 20_000.0..=80_000.0;
+
+/// 编译后的转换步骤
+pub(crate) enum Step {
+    Trim,
+    StripPrefix(String),
+    IsoDate,
+    Split { separators: Vec<String>, join: String },
+    Lookup { table: HashMap<String, String>, default: Option<String> },
+}
+
+impl Step {
+    fn new(
+        transform: &Transform,
+        lookup_tables: &HashMap<String, HashMap<String, String>>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(match transform {
+            Transform::Trim => Step::Trim,
+            Transform::StripPrefix { prefix } => Step::StripPrefix(prefix.to_lowercase()),
+            Transform::IsoDate => Step::IsoDate,
+            Transform::Split { separators, join } => {
+                if separators.iter().any(String::is_empty) {
+                    return Err("split 转换的分隔符不能为空".into());
+                }
+                Step::Split {
+                    separators: separators.clone(),
+                    join: join.clone(),
+                }
+            }
+            Transform::Lookup { table, default } => {
+                let table = lookup_tables
+                    .get(table)
+                    .ok_or_else(|| format!("未定义的值对照表: {}", table))?;
+                Step::Lookup {
+                    table: table.clone(),
+                    default: default.clone(),
+                }
+            }
+        })
+    }
+
+    fn apply(&self, value: String) -> String {
+        match self {
+            Step::Trim => value.trim().to_string(),
+            Step::StripPrefix(prefix) => strip_prefix_ignore_case(&value, prefix)
+                .map(str::to_string)
+                .unwrap_or(value),
+            Step::IsoDate => match parse_date(&value) {
+                Some(date) => date.format("%Y-%m-%d").to_string(),
+                None => {
+                    if !value.trim().is_empty() {
+                        tracing::debug!("无法识别的日期: {}", value);
+                    }
+                    value
+                }
+            },
+            Step::Split { separators, join } => {
+                let mut parts = vec![value.as_str()];
+                for separator in separators {
+                    parts = parts.iter().flat_map(|part| part.split(separator.as_str())).collect();
+                }
+                parts
+                    .iter()
+                    .map(|part| part.trim())
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(join)
+            }
+            Step::Lookup { table, default } => match table.get(value.trim()) {
+                Some(mapped) => mapped.clone(),
+                None => default.clone().unwrap_or(value),
+            },
+        }
+    }
+}
+
+/// 列值转换器
+pub(crate) struct ValueTransformer {
+    columns: HashMap<String, Vec<Step>>,
+}
+
+impl ValueTransformer {
+    pub fn new(
+        transforms: &[ColumnTransform],
+        lookup_tables: &HashMap<String, HashMap<String, String>>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let mut columns: HashMap<String, Vec<Step>> = HashMap::new();
+        for transform in transforms {
+            tracing::debug!("列值转换: {} ({} 个步骤)", transform.column, transform.steps.len());
+            let steps = columns.entry(transform.column.clone()).or_default();
+            for step in &transform.steps {
+                steps.push(Step::new(step, lookup_tables)?);
+            }
+        }
+        Ok(Self { columns })
+    }
+
+    /// 按表头顺序取出每一列的转换步骤
+    pub fn plan<'a>(&'a self, headers: &[String]) -> Vec<Option<&'a [Step]>> {
+        headers
+            .iter()
+            .map(|header| self.columns.get(header).map(Vec::as_slice))
+            .collect()
+    }
+}
+
+/// 依次执行转换步骤
+pub(crate) fn apply_steps(steps: &[Step], value: String) -> String {
+    steps.iter().fold(value, |value, step| step.apply(value))
+}
+
+fn strip_prefix_ignore_case<'a>(value: &'a str, prefix: &str) -> Option<&'a str> {
+    let trimmed = value.trim_start();
+    let head = trimmed.get(..prefix.len())?;
+    if head.to_lowercase() == prefix {
+        Some(&trimmed[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+/// 解析常见的日期写法及 Excel 日期序列号
+pub(crate) fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    // 去掉时间部分，例如 "2023-05-01 00:00:00"
+    let date_part = value.split([' ', 'T']).next().unwrap_or(value);
+
+    // 紧凑格式 20230501
+    if date_part.len() == 8 && date_part.bytes().all(|b| b.is_ascii_digit()) {
+        return NaiveDate::parse_from_str(date_part, "%Y%m%d").ok();
+    }
+
+    let parts: Vec<&str> = date_part
+        .split(['.', '/', '-', '年', '月', '日'])
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.len() == 3 {
+        // 年份必须为 4 位，月、日为 1 到 2 位，避免 "1.2.3" 之类的版本号被当作日期
+        let digits = |part: &str, len: std::ops::RangeInclusive<usize>| {
+            len.contains(&part.len()) && part.bytes().all(|b| b.is_ascii_digit())
+        };
+        if !(digits(parts[0], 4..=4) && digits(parts[1], 1..=2) && digits(parts[2], 1..=2)) {
+            return None;
+        }
+        let year = parts[0].parse().ok()?;
+        let month = parts[1].parse().ok()?;
+        let day = parts[2].parse().ok()?;
+        // 月、日超出范围（如 2023-13-01、2023-02-30）时 from_ymd_opt 返回 None
+        return NaiveDate::from_ymd_opt(year, month, day);
+    }
+
+    // Excel 日期序列号，只接受纯数字且在合理范围内的值，小数部分为时间
+    let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if integer.is_empty() || !is_digits(integer) || !is_digits(fraction) {
+        return None;
+    }
+    let serial: f64 = value.parse().ok()?;
+    if EXCEL_SERIAL_RANGE.contains(&serial) {
+        let (year, month, day) = EXCEL_EPOCH;
+        return NaiveDate::from_ymd_opt(year, month, day)?
+            .checked_add_signed(Duration::days(serial.trunc() as i64));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> Option<String> {
+        parse_date(value).map(|d| d.format("%Y-%m-%d").to_string())
+    }
+
+    #[test]
+    fn parses_common_formats() {
+        assert_eq!(date("2023.05.01").as_deref(), Some("2023-05-01"));
+        assert_eq!(date("2023/5/1").as_deref(), Some("2023-05-01"));
+        assert_eq!(date("2023年5月1日").as_deref(), Some("2023-05-01"));
+        assert_eq!(date("20230501").as_deref(), Some("2023-05-01"));
+        assert_eq!(date("2023-05-01 00:00:00").as_deref(), Some("2023-05-01"));
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        assert_eq!(date("1.2.3"), None);
+        assert_eq!(date("23.5.1"), None);
+        assert_eq!(date("2023.005.01"), None);
+        assert_eq!(date("2023-13-01"), None);
+        assert_eq!(date("2023-02-30"), None);
+        assert_eq!(date("2023/0/1"), None);
+        assert_eq!(date("+2023-05-01"), None);
+    }
+
+    #[test]
+    fn parses_excel_serials() {
+        assert_eq!(date("45047").as_deref(), Some("2023-05-01"));
+        assert_eq!(date("45047.5").as_deref(), Some("2023-05-01"));
+    }
+
+    #[test]
+    fn rejects_numbers_outside_serial_range() {
+        assert_eq!(date("2023"), None);
+        assert_eq!(date("2023.05"), None);
+        assert_eq!(date("1"), None);
+        assert_eq!(date("100000"), None);
+        assert_eq!(date("-45047"), None);
+        assert_eq!(date("4.5e4"), None);
+        assert_eq!(date(""), None);
+    }
+}
@@ -0,0 +1,402 @@
+use chrono::NaiveDate;
+use std::path::Path;
+use std::sync::Arc;
+use crate::cli::ImportMode;
+use crate::commands::types::{
+    ColumnRule, FileValidation, ValidationIssue, ValidationReport, ValidationSchema, ValueType,
+};
+use calamine::Data;
+use super::application_number::{compute_check_digit, ApplicationNumber};
+use super::cell_to_string;
+
+/// 默认日期格式
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// 按校验规则检查文件
+pub async fn validate_files(
+    files: &[String],
+    schema: &ValidationSchema,
+    import_mode: ImportMode,
+) -> Result<ValidationReport, Box<dyn std::error::Error + Send + Sync>> {
+    let rules = Arc::new(schema.rules_for(import_mode));
+    tracing::info!("开始校验文件，文件数: {}, 导入模式: {}, 规则数: {}", files.len(), import_mode, rules.len());
+
+    let mut results = Vec::new();
+    for (index, file_path) in files.iter().enumerate() {
+        tracing::info!("正在校验文件 {}/{}: {}", index + 1, files.len(), file_path);
+
+        let file = file_path.clone();
+        let file_rules = Arc::clone(&rules);
+        let result = tokio::task::spawn_blocking(move || check_file(&file, &file_rules)).await?;
+
+        let validation = match result {
+            Ok(validation) => validation,
+            Err(e) => {
+                tracing::error!("读取文件失败 {}: {}", file_path, e);
+                FileValidation {
+                    file: file_path.clone(),
+                    passed: false,
+                    rows_checked: 0,
+                    issues: vec![ValidationIssue {
+                        file: file_path.clone(),
+                        sheet: String::new(),
+                        row: 0,
+                        column: String::new(),
+                        value: String::new(),
+                        message: format!("读取文件失败: {}", e),
+                    }],
+                }
+            }
+        };
+
+        if validation.passed {
+            tracing::info!("文件校验通过: {}", file_path);
+        } else {
+            tracing::warn!("文件校验未通过: {}，共 {} 个问题", file_path, validation.issues.len());
+        }
+        results.push(validation);
+    }
+
+    Ok(ValidationReport {
+        import_mode,
+        files: results,
+    })
+}
+
+/// 校验单个文件的所有工作表
+fn check_file(
+    file_path: &str,
+    rules: &[ColumnRule],
+) -> Result<FileValidation, Box<dyn std::error::Error + Send + Sync>> {
+    let mut workbook = super::format::open_workbook(file_path)?;
+    let mut issues = Vec::new();
+    let mut rows_checked = 0;
+
+    for sheet_name in workbook.sheet_names().to_owned() {
+        // 无法读取的工作表记为一个问题，继续校验其他工作表
+        let range = match workbook.worksheet_range(&sheet_name) {
+            Ok(range) => range,
+            Err(e) => {
+                tracing::warn!("读取工作表失败 {} [{}]: {}", file_path, sheet_name, e);
+                issues.push(ValidationIssue {
+                    file: file_path.to_string(),
+                    sheet: sheet_name.clone(),
+                    row: 0,
+                    column: String::new(),
+                    value: String::new(),
+                    message: format!("读取工作表失败: {}", e),
+                });
+                continue;
+            }
+        };
+        let header_row = range.start().map(|(row, _)| row as usize).unwrap_or(0);
+        let mut rows = range.rows();
+
+        // 空工作表没有可导入的数据
+        let headers: Vec<String> = match rows.next() {
+            Some(header) => header.iter().map(cell_to_string).collect(),
+            None => continue,
+        };
+
+        let mut issue = |row: usize, column: &str, value: String, message: String| {
+            issues.push(ValidationIssue {
+                file: file_path.to_string(),
+                sheet: sheet_name.clone(),
+                row,
+                column: column.to_string(),
+                value,
+                message,
+            });
+        };
+
+        let mut columns = Vec::new();
+        for rule in rules {
+            match headers.iter().position(|h| h == &rule.name) {
+                Some(index) => columns.push((rule, index)),
+                None if rule.required => issue(header_row + 1, &rule.name, String::new(), "缺少必需列".to_string()),
+                None => {}
+            }
+        }
+
+        for (row_index, row) in rows.enumerate() {
+            rows_checked += 1;
+            for (rule, index) in &columns {
+                // 申请号列与 validate_application_numbers 相同，保留数值单元格中的校验位 0
+                let text = match rule.value_type {
+                    ValueType::ApplicationNumber => application_number_text,
+                    _ => cell_to_string,
+                };
+                let value = row.get(*index).map(text).unwrap_or_default();
+                if let Some(message) = check_value(rule, value.trim()) {
+                    issue(header_row + row_index + 2, &rule.name, value, message);
+                }
+            }
+        }
+    }
+
+    Ok(FileValidation {
+        file: file_path.to_string(),
+        passed: issues.is_empty(),
+        rows_checked,
+        issues,
+    })
+}
+
+/// 按列规则检查单个值，返回问题描述
+fn check_value(rule: &ColumnRule, value: &str) -> Option<String> {
+    if value.is_empty() {
+        return rule.not_empty.then(|| "值不能为空".to_string());
+    }
+
+    let type_error = match rule.value_type {
+        ValueType::Text => None,
+        ValueType::Integer => value.parse::<i64>().err().map(|_| "应为整数".to_string()),
+        ValueType::Number => value.parse::<f64>().err().map(|_| "应为数字".to_string()),
+        ValueType::Date => {
+            let format = rule.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT);
+            NaiveDate::parse_from_str(value, format)
+                .err()
+                .map(|_| format!("日期格式应为 {}", format))
+        }
+        ValueType::ApplicationNumber => ApplicationNumber::parse(value).err(),
+    };
+    if type_error.is_some() {
+        return type_error;
+    }
+
+    if !rule.allowed_values.is_empty() && !rule.allowed_values.iter().any(|v| v == value) {
+        return Some(format!("不在允许的取值中: {}", rule.allowed_values.join(", ")));
+    }
+
+    None
+}
+
+/// 写出校验报告，按扩展名选择 JSON 或 xlsx 格式
+pub fn write_validation_report(
+    report: &ValidationReport,
+    path: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    tracing::info!("写出校验报告: {}", path);
+
+    let extension = Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "json" => std::fs::write(path, serde_json::to_string_pretty(report)?)?,
+        "xlsx" => write_report_xlsx(report, path)?,
+        _ => return Err(format!("不支持的报告格式: {}（仅支持 .json 和 .xlsx）", path).into()),
+    }
+
+    Ok(())
+}
+
+fn write_report_xlsx(
+    report: &ValidationReport,
+    path: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let workbook = xlsxwriter::Workbook::new(path)?;
+
+    let mut summary = workbook.add_worksheet(Some("汇总"))?;
+    for (col, header) in ["文件", "结果", "校验行数", "问题数"].iter().enumerate() {
+        summary.write_string(0, col as u16, header, None)?;
+    }
+    for (index, file) in report.files.iter().enumerate() {
+        let row = index as u32 + 1;
+        summary.write_string(row, 0, &file.file, None)?;
+        summary.write_string(row, 1, if file.passed { "通过" } else { "未通过" }, None)?;
+        summary.write_number(row, 2, file.rows_checked as f64, None)?;
+        summary.write_number(row, 3, file.issues.len() as f64, None)?;
+    }
+
+    let mut details = workbook.add_worksheet(Some("问题"))?;
+    for (col, header) in ["文件", "工作表", "行号", "列", "值", "问题"].iter().enumerate() {
+        details.write_string(0, col as u16, header, None)?;
+    }
+    let issues = report.files.iter().flat_map(|f| f.issues.iter());
+    for (index, issue) in issues.enumerate() {
+        let row = index as u32 + 1;
+        details.write_string(row, 0, &issue.file, None)?;
+        details.write_string(row, 1, &issue.sheet, None)?;
+        details.write_number(row, 2, issue.row as f64, None)?;
+        details.write_string(row, 3, &issue.column, None)?;
+        details.write_string(row, 4, &issue.value, None)?;
+        details.write_string(row, 5, &issue.message, None)?;
+    }
+
+    workbook.close()?;
+    Ok(())
+}
+
+/// 校验文件中的申请号（格式、年份、专利类型和校验位）
+pub async fn validate_application_numbers(
+    files: &[String],
+    column: &str,
+) -> Result<Vec<ValidationIssue>, Box<dyn std::error::Error + Send + Sync>> {
+    tracing::info!("开始校验申请号，文件数: {}, 申请号列: {}", files.len(), column);
+
+    let mut issues = Vec::new();
+    for file_path in files {
+        let file = file_path.clone();
+        let file_column = column.to_string();
+        let file_issues = tokio::task::spawn_blocking(move || {
+            check_application_numbers(&file, &file_column)
+        }).await?;
+        let file_issues = file_issues.unwrap_or_else(|e| {
+            tracing::error!("读取文件失败 {}: {}", file_path, e);
+            vec![ValidationIssue {
+                file: file_path.clone(),
+                sheet: String::new(),
+                row: 0,
+                column: column.to_string(),
+                value: String::new(),
+                message: format!("读取文件失败: {}", e),
+            }]
+        });
+
+        if !file_issues.is_empty() {
+            tracing::warn!("文件 {} 中有 {} 个无效申请号", file_path, file_issues.len());
+        }
+        issues.extend(file_issues);
+    }
+
+    tracing::info!("申请号校验完成，共 {} 个问题", issues.len());
+
+    Ok(issues)
+}
+
+/// 校验单个文件中所有工作表的申请号列
+fn check_application_numbers(
+    file_path: &str,
+    column: &str,
+) -> Result<Vec<ValidationIssue>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut workbook = super::format::open_workbook(file_path)?;
+    let mut issues = Vec::new();
+
+    for sheet_name in workbook.sheet_names().to_owned() {
+        // 无法读取的工作表记为一个问题，继续校验其他工作表
+        let range = match workbook.worksheet_range(&sheet_name) {
+            Ok(range) => range,
+            Err(e) => {
+                tracing::warn!("读取工作表失败 {} [{}]: {}", file_path, sheet_name, e);
+                issues.push(ValidationIssue {
+                    file: file_path.to_string(),
+                    sheet: sheet_name.clone(),
+                    row: 0,
+                    column: column.to_string(),
+                    value: String::new(),
+                    message: format!("读取工作表失败: {}", e),
+                });
+                continue;
+            }
+        };
+        let header_row = range.start().map(|(row, _)| row as usize).unwrap_or(0);
+        let mut rows = range.rows();
+
+        let column_index = match rows
+            .next()
+            .and_then(|header| header.iter().position(|cell| cell_to_string(cell) == column))
+        {
+            Some(index) => index,
+            None => {
+                tracing::debug!("工作表 {} 中没有申请号列 {}", sheet_name, column);
+                continue;
+            }
+        };
+
+        for (row_index, row) in rows.enumerate() {
+            let value = row.get(column_index).map(application_number_text).unwrap_or_default();
+            if value.trim().is_empty() {
+                continue;
+            }
+            if let Err(message) = ApplicationNumber::parse(&value) {
+                issues.push(ValidationIssue {
+                    file: file_path.to_string(),
+                    sheet: sheet_name.clone(),
+                    row: header_row + row_index + 2,
+                    column: column.to_string(),
+                    value,
+                    message,
+                });
+            }
+        }
+    }
+
+    Ok(issues)
+}
+
+/// 读取申请号单元格的文本
+///
+/// 数值单元格中的校验位 ".0" 会被丢掉（201310123403.0 读出为 201310123403），
+/// 因此 12 位或 8 位的整数在补上校验位 0 后正确时，按补全后的申请号校验。
+fn application_number_text(cell: &Data) -> String {
+    let text = cell_to_string(cell);
+    let is_integer = match cell {
+        Data::Int(_) => true,
+        Data::Float(f) => f.fract() == 0.0,
+        _ => false,
+    };
+    if is_integer && matches!(text.len(), 8 | 12) && compute_check_digit(&text) == '0' {
+        return format!("{}.0", text);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::excel::testing::{temp_path, write_workbook};
+
+    fn rule(name: &str, value_type: ValueType) -> ColumnRule {
+        ColumnRule {
+            name: name.to_string(),
+            required: true,
+            not_empty: false,
+            value_type,
+            date_format: None,
+            allowed_values: Vec::new(),
+            aliases: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn check_file_keeps_zero_check_digit_and_reports_unreadable_sheets() {
+        let path = temp_path("xlsx");
+        write_workbook(
+            &path,
+            &[("数据", &[&["申请号"], &["201310123403"], &["CN201310123403.0"], &["201310123456"]])],
+            &["损坏"],
+        );
+        let file = path.to_string_lossy().to_string();
+
+        let result = check_file(&file, &[rule("申请号", ValueType::ApplicationNumber)]);
+        std::fs::remove_file(&path).unwrap();
+        let validation = result.unwrap();
+
+        assert_eq!(validation.rows_checked, 3);
+        let issues: Vec<(&str, usize)> = validation.issues.iter().map(|i| (i.sheet.as_str(), i.row)).collect();
+        // 数值单元格 201310123403 补上校验位后正确；201310123456 仍缺少校验位
+        assert_eq!(issues, vec![("数据", 4), ("损坏", 0)]);
+        assert!(validation.issues[1].message.contains("读取工作表失败"));
+    }
+
+    #[test]
+    fn restores_zero_check_digit_of_numeric_cells() {
+        assert_eq!(application_number_text(&Data::Float(201310123403.0)), "201310123403.0");
+        assert_eq!(application_number_text(&Data::Int(98100001)), "98100001.0");
+        assert!(ApplicationNumber::parse(&application_number_text(&Data::Float(201310123403.0))).is_ok());
+    }
+
+    #[test]
+    fn keeps_other_numeric_cells() {
+        // 补上校验位 0 不正确时保持原值，仍报告缺少校验位
+        assert_eq!(application_number_text(&Data::Float(201310123456.0)), "201310123456");
+        assert_eq!(application_number_text(&Data::Float(201310123456.5)), "201310123456.5");
+        assert_eq!(application_number_text(&Data::Float(2023.0)), "2023");
+        assert_eq!(
+            application_number_text(&Data::String("201310123403".to_string())),
+            "201310123403"
+        );
+    }
+}
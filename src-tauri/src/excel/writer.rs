@@ -0,0 +1,369 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use crate::commands::types::{OutputFormat, SplitConfig, TextEncoding};
+
+/// UTF-8 BOM
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// 转换结果的输出文件，按需拆分为多个分卷
+pub(crate) struct OutputWriter {
+    target: PathBuf,
+    format: OutputFormat,
+    encoding: TextEncoding,
+    /// CSV/TSV 输出时是否只写了一个工作表，此时文件名不带工作表名
+    single_sheet: bool,
+    parts: Parts,
+    /// xlsx 输出时当前分卷的工作簿
+    workbook: Option<xlsxwriter::Workbook>,
+    /// CSV/TSV 输出时当前文件对应的工作表
+    current_sheet: Option<String>,
+    /// 合并小文件时先收集到内存，不写文件
+    collected: Option<Vec<CollectedSheet>>,
+    /// 试运行：按相同的规则命名和拆分，但不创建、写入或重命名文件
+    dry_run: bool,
+    outputs: Vec<PathBuf>,
+}
+
+/// 收集到内存中的工作表
+pub(crate) struct CollectedSheet {
+    pub name: String,
+    pub headers: Vec<String>,
+    /// 源文件中的行号和转换后的值
+    pub rows: Vec<(usize, Vec<String>)>,
+}
+
+/// 正在写入的工作表
+pub(crate) struct SheetWriter<'a> {
+    sink: Sink<'a>,
+    parts: &'a mut Parts,
+    next_row: u32,
+}
+
+enum Sink<'a> {
+    Xlsx(xlsxwriter::Worksheet<'a>),
+    Text(Box<csv::Writer<File>>),
+    Memory(&'a mut CollectedSheet),
+    /// 试运行时只统计分卷用量
+    Discard,
+}
+
+/// 当前分卷的用量
+struct Parts {
+    max_rows: Option<usize>,
+    max_bytes: Option<usize>,
+    /// 当前分卷序号，从 1 开始
+    index: usize,
+    rows: usize,
+    bytes: usize,
+}
+
+impl OutputWriter {
+    /// 创建输出，`target` 为不含工作表名和分卷序号的输出路径
+    pub fn create(
+        target: &Path,
+        format: OutputFormat,
+        encoding: TextEncoding,
+        split: Option<&SplitConfig>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::open(target, format, encoding, split, false)
+    }
+
+    /// 创建试运行的输出，只计算会生成的文件，不写出任何文件
+    pub fn plan(
+        target: &Path,
+        format: OutputFormat,
+        split: Option<&SplitConfig>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::open(target, format, TextEncoding::default(), split, true)
+    }
+
+    fn open(
+        target: &Path,
+        format: OutputFormat,
+        encoding: TextEncoding,
+        split: Option<&SplitConfig>,
+        dry_run: bool,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let mut writer = Self {
+            target: target.to_path_buf(),
+            format,
+            encoding,
+            single_sheet: true,
+            parts: Parts {
+                max_rows: split.and_then(|s| s.max_rows),
+                max_bytes: split.and_then(|s| s.max_megabytes).map(|mb| (mb * 1024.0 * 1024.0) as usize),
+                index: 1,
+                rows: 0,
+                bytes: 0,
+            },
+            workbook: None,
+            current_sheet: None,
+            collected: None,
+            dry_run,
+            outputs: Vec::new(),
+        };
+
+        if format == OutputFormat::Xlsx {
+            writer.open_workbook(writer.file_path(None, None))?;
+        }
+
+        Ok(writer)
+    }
+
+    /// 创建只收集数据、不写文件的输出
+    pub fn collect() -> Self {
+        Self {
+            target: PathBuf::new(),
+            format: OutputFormat::Xlsx,
+            encoding: TextEncoding::default(),
+            single_sheet: false,
+            parts: Parts {
+                max_rows: None,
+                max_bytes: None,
+                index: 1,
+                rows: 0,
+                bytes: 0,
+            },
+            workbook: None,
+            current_sheet: None,
+            collected: Some(Vec::new()),
+            dry_run: false,
+            outputs: Vec::new(),
+        }
+    }
+
+    /// 取出收集到的工作表
+    pub fn take_collected(&mut self) -> Vec<CollectedSheet> {
+        self.collected.take().unwrap_or_default()
+    }
+
+    /// 当前分卷是否已满；CSV/TSV 每个工作表单独成文件，不在工作表之间拆分
+    pub fn is_full(&self) -> bool {
+        self.format == OutputFormat::Xlsx && self.parts.is_full()
+    }
+
+    /// 添加工作表并写入表头
+    pub fn add_sheet(
+        &mut self,
+        name: &str,
+        headers: &[String],
+    ) -> Result<SheetWriter<'_>, Box<dyn std::error::Error + Send + Sync>> {
+        if self.collected.is_some() {
+            let collected = self.collected.get_or_insert_with(Vec::new);
+            collected.push(CollectedSheet {
+                name: name.to_string(),
+                headers: headers.to_vec(),
+                rows: Vec::new(),
+            });
+            let sheet = collected.last_mut().ok_or("输出文件已关闭")?;
+            return Ok(SheetWriter {
+                sink: Sink::Memory(sheet),
+                parts: &mut self.parts,
+                next_row: 1,
+            });
+        }
+
+        let sink = match self.format {
+            OutputFormat::Xlsx if self.dry_run => Sink::Discard,
+            OutputFormat::Xlsx => {
+                let workbook = self.workbook.as_ref().ok_or("输出文件已关闭")?;
+                Sink::Xlsx(workbook.add_worksheet(Some(name))?)
+            }
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                // 同一工作表的后续分卷沿用分卷序号，新的工作表重新计数
+                let continued = self.current_sheet.as_deref() == Some(name);
+                if !continued {
+                    if self.current_sheet.is_some() && self.single_sheet {
+                        self.add_sheet_names()?;
+                    }
+                    self.parts.index = 1;
+                    self.parts.reset();
+                    self.current_sheet = Some(name.to_string());
+                }
+                let sheet = (!self.single_sheet).then_some(name);
+                let part = continued.then_some(self.parts.index);
+                let path = self.file_path(sheet, part);
+                let sink = if self.dry_run {
+                    Sink::Discard
+                } else {
+                    let mut file = File::create(&path)?;
+                    if self.encoding == TextEncoding::Utf8Bom {
+                        file.write_all(UTF8_BOM)?;
+                    }
+                    let writer = csv::WriterBuilder::new()
+                        .delimiter(self.format.delimiter())
+                        .flexible(true)
+                        .from_writer(file);
+                    Sink::Text(Box::new(writer))
+                };
+                self.outputs.push(path);
+                sink
+            }
+        };
+
+        let mut sheet = SheetWriter {
+            sink,
+            parts: &mut self.parts,
+            next_row: 0,
+        };
+        sheet.write(headers)?;
+        Ok(sheet)
+    }
+
+    /// 结束当前分卷，之后的数据写入下一个分卷
+    ///
+    /// 第一次拆分时将已写完的文件重命名为 `.part-001`，未拆分的文件保持原名。
+    pub fn next_part(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(workbook) = self.workbook.take() {
+            workbook.close()?;
+        }
+
+        let sheet = match self.format {
+            OutputFormat::Xlsx => None,
+            _ => self.current_sheet.clone().filter(|_| !self.single_sheet),
+        };
+        if self.parts.index == 1 {
+            let renamed = self.file_path(sheet.as_deref(), Some(1));
+            if let Some(last) = self.outputs.last_mut() {
+                if !self.dry_run {
+                    std::fs::rename(&*last, &renamed)?;
+                }
+                *last = renamed;
+            }
+        }
+
+        self.parts.index += 1;
+        self.parts.reset();
+        tracing::info!("输出文件达到拆分上限，开始第 {} 个分卷: {}", self.parts.index, self.target.display());
+
+        if self.format == OutputFormat::Xlsx {
+            self.open_workbook(self.file_path(None, Some(self.parts.index)))?;
+        }
+        Ok(())
+    }
+
+    /// 完成写入，返回生成的文件
+    pub fn close(mut self) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(workbook) = self.workbook.take() {
+            workbook.close().map_err(|e| {
+                tracing::error!("关闭 Excel 文件失败: {}", e);
+                e
+            })?;
+        }
+        Ok(self.outputs)
+    }
+
+    /// 开始写第二个工作表时，为第一个工作表已写出的文件补上工作表名
+    ///
+    /// 是否只有一个工作表要到过滤完成后才知道，因此先按单个工作表命名。
+    fn add_sheet_names(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.single_sheet = false;
+        let sheet = self.current_sheet.clone().unwrap_or_default();
+        let split = self.outputs.len() > 1;
+        for index in 0..self.outputs.len() {
+            let renamed = self.file_path(Some(&sheet), split.then_some(index + 1));
+            if !self.dry_run {
+                std::fs::rename(&self.outputs[index], &renamed)?;
+            }
+            self.outputs[index] = renamed;
+        }
+        Ok(())
+    }
+
+    fn open_workbook(&mut self, path: PathBuf) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if self.dry_run {
+            self.outputs.push(path);
+            return Ok(());
+        }
+        let workbook = xlsxwriter::Workbook::new(&path.to_string_lossy())
+            .map_err(|e| {
+                tracing::error!("创建新 Excel 文件失败: {}", e);
+                e
+            })?;
+        self.workbook = Some(workbook);
+        self.outputs.push(path);
+        Ok(())
+    }
+
+    /// 输出文件路径：`文件名[.工作表名][.part-001].扩展名`
+    fn file_path(&self, sheet: Option<&str>, part: Option<usize>) -> PathBuf {
+        let mut name = self
+            .target
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if let Some(sheet) = sheet {
+            name.push('.');
+            name.push_str(&sanitize_file_name(sheet));
+        }
+        if let Some(part) = part {
+            name.push_str(&format!(".part-{:03}", part));
+        }
+        name.push('.');
+        name.push_str(self.format.extension());
+        self.target.with_file_name(name)
+    }
+}
+
+impl SheetWriter<'_> {
+    /// 当前分卷是否已满
+    pub fn is_full(&self) -> bool {
+        self.parts.is_full()
+    }
+
+    /// 写入一行数据，`source_row` 为源文件中的行号
+    pub fn write_row(&mut self, source_row: usize, values: &[String]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Sink::Memory(sheet) = &mut self.sink {
+            sheet.rows.push((source_row, values.to_vec()));
+            return Ok(());
+        }
+        self.write(values)?;
+        self.parts.rows += 1;
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Sink::Text(mut writer) = self.sink {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, values: &[String]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match &mut self.sink {
+            Sink::Xlsx(worksheet) => {
+                for (col_index, value) in values.iter().enumerate() {
+                    worksheet.write_string(self.next_row, col_index as u16, value, None)?;
+                }
+            }
+            Sink::Text(writer) => writer.write_record(values)?,
+            // 内存中的表头在添加工作表时记录，数据行由 write_row 记录
+            Sink::Memory(_) | Sink::Discard => {}
+        }
+        self.next_row += 1;
+        self.parts.bytes += values.iter().map(|v| v.len() + 1).sum::<usize>();
+        Ok(())
+    }
+}
+
+impl Parts {
+    /// 至少写入一行数据后才判断是否已满，避免只有表头的分卷
+    fn is_full(&self) -> bool {
+        self.rows > 0
+            && (self.max_rows.is_some_and(|max| self.rows >= max)
+                || self.max_bytes.is_some_and(|max| self.bytes >= max))
+    }
+
+    fn reset(&mut self) {
+        self.rows = 0;
+        self.bytes = 0;
+    }
+}
+
+/// 替换文件名中不允许的字符
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+        .collect()
+}
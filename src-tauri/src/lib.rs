@@ -29,8 +29,18 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             scan_excel_files,
+            inspect_excel_files,
+            preview_workbook,
+            preview_mapping,
+            suggest_mappings,
             convert_excel_files,
+            find_duplicate_patents,
             get_converted_files,
+            validate_application_numbers,
+            validate_files,
+            load_schema,
+            load_convert_config,
+            check_mappings,
             save_log_file,
             upload_file
         ])
@@ -1,7 +1,18 @@
 import { ref, computed, readonly } from 'vue';
 import { invoke } from '@tauri-apps/api/core';
 import { open } from '@tauri-apps/plugin-dialog';
-import type { ScanResult, ColumnMapping } from '@/types';
+import type {
+  ScanResult,
+  ColumnMapping,
+  ConvertedFile,
+  FileEntry,
+  WorkbookPreview,
+  MappingPreview,
+  MappingSuggestions,
+  ValidationSchema,
+  SchemaCheck,
+  ImportMode
+} from '@/types';
 import { useLoggerStore } from '@/stores/logger';
 
 export function useFileOperations() {
@@ -13,6 +24,7 @@ export function useFileOperations() {
   const convertedFiles = ref<File[]>([]);
   const scanning = ref(false);
   const converting = ref(false);
+  const inspecting = ref(false);
   
   const canConvert = computed(() => {
     // 如果没有扫描结果或没有文件，不能转换
@@ -70,7 +82,7 @@ export function useFileOperations() {
     }
   }
   
-  async function scanFiles() {
+  async function scanFiles(mappings: ColumnMapping[] = []) {
     if (!sourcePath.value) {
       loggerStore.warn('请先选择源文件夹');
       return;
@@ -85,6 +97,11 @@ export function useFileOperations() {
       });
       scanResult.value = result;
       loggerStore.success(`扫描完成，发现 ${result.file_count} 个Excel文件，总大小: ${(result.total_size / 1024 / 1024).toFixed(2)} MB`);
+      for (const rejected of result.rejected_files) {
+        loggerStore.warn(`跳过文件 ${rejected.path}: ${rejected.reason}`);
+      }
+      // 工作表和行数在后台读取，不阻塞扫描结果的显示
+      void inspectFiles(mappings);
     } catch (error) {
       const errorMsg = `扫描文件失败: ${error instanceof Error ? error.message : String(error)}`;
       loggerStore.error(errorMsg);
@@ -95,14 +112,110 @@ export function useFileOperations() {
     }
   }
   
-  async function startConversion(mappings: ColumnMapping[]) {
+  async function inspectFiles(mappings: ColumnMapping[] = []) {
+    const result = scanResult.value;
+    if (!result || result.files.length === 0) {
+      return;
+    }
+    
+    inspecting.value = true;
+    try {
+      const entries = await invoke<FileEntry[]>('inspect_excel_files', {
+        files: result.files,
+        mappings
+      });
+      // 扫描结果可能已被新的扫描替换
+      if (scanResult.value === result) {
+        scanResult.value = { ...result, entries };
+      }
+      for (const entry of entries.filter((e) => e.error)) {
+        loggerStore.warn(`读取文件信息失败 ${entry.path}: ${entry.error}`);
+      }
+    } catch (error) {
+      loggerStore.warn(`读取文件信息失败: ${error instanceof Error ? error.message : String(error)}`);
+    } finally {
+      inspecting.value = false;
+    }
+  }
+  
+  async function previewWorkbook(path: string, sheet?: string, limit?: number) {
+    try {
+      return await invoke<WorkbookPreview>('preview_workbook', { path, sheet, limit });
+    } catch (error) {
+      loggerStore.error(`预览文件失败 ${path}: ${error instanceof Error ? error.message : String(error)}`);
+      return null;
+    }
+  }
+  
+  async function previewMapping(path: string, mappings: ColumnMapping[], sheet?: string, limit?: number) {
+    try {
+      return await invoke<MappingPreview>('preview_mapping', { path, sheet, limit, mappings });
+    } catch (error) {
+      loggerStore.error(`预览列映射失败 ${path}: ${error instanceof Error ? error.message : String(error)}`);
+      return null;
+    }
+  }
+  
+  async function suggestMappings(files: string[], targets: string[], schema?: ValidationSchema | null, minScore?: number) {
+    try {
+      const result = await invoke<MappingSuggestions>('suggest_mappings', { files, targets, schema, minScore });
+      loggerStore.info(`共 ${result.headers.length} 个不同的表头，${result.suggestions.length} 条映射建议`);
+      return result;
+    } catch (error) {
+      loggerStore.error(`生成列映射建议失败: ${error instanceof Error ? error.message : String(error)}`);
+      return null;
+    }
+  }
+  
+  async function selectSchemaFile() {
+    const selected = await open({
+      title: '选择目标列定义文件',
+      filters: [{ name: '目标列定义', extensions: ['json'] }]
+    });
+    return typeof selected === 'string' ? selected : null;
+  }
+  
+  async function loadSchema(path: string) {
+    try {
+      const schema = await invoke<ValidationSchema>('load_schema', { path });
+      loggerStore.info(`已读取目标列定义 ${path}（版本 ${schema.version}，${schema.columns.length} 列）`);
+      return schema;
+    } catch (error) {
+      loggerStore.error(`读取目标列定义失败: ${error instanceof Error ? error.message : String(error)}`);
+      return null;
+    }
+  }
+  
+  async function checkMappings(mappings: ColumnMapping[], schema: ValidationSchema, importMode?: ImportMode) {
+    try {
+      const check = await invoke<SchemaCheck>('check_mappings', { mappings, schema, importMode });
+      for (const rename of check.migrated) {
+        loggerStore.info(`迁移列名: ${rename.from} -> ${rename.to}`);
+      }
+      if (check.alias_mappings.length > 0) {
+        loggerStore.info(`按别名添加 ${check.alias_mappings.length} 个列映射`);
+      }
+      for (const issue of check.issues) {
+        loggerStore.warn(issue);
+      }
+      return check;
+    } catch (error) {
+      loggerStore.error(`检查列映射失败: ${error instanceof Error ? error.message : String(error)}`);
+      return null;
+    }
+  }
+  
+  async function startConversion(mappings: ColumnMapping[], schema?: ValidationSchema | null, importMode?: ImportMode) {
     if (!canConvert.value) {
       loggerStore.warn('请先扫描文件');
       return;
     }
     
-    // 如果没有列映射，直接返回扫描到的原始文件
-    if (mappings.length === 0) {
+    // CSV/TSV 文件需要先转换为 xlsx 才能上传
+    const hasTextInputs = scanResult.value?.entries.some((entry) => entry.format === 'csv' || entry.format === 'tsv') ?? false;
+    
+    // 如果没有列映射和目标列定义，直接返回扫描到的原始文件
+    if (mappings.length === 0 && !schema && !hasTextInputs) {
       loggerStore.info('未配置列映射，将直接上传原始文件');
       converting.value = true;
       
@@ -150,13 +263,40 @@ export function useFileOperations() {
     loggerStore.info(`开始转换文件，使用 ${mappings.length} 个列映射规则`);
     
     try {
-      const convertedFilePaths = await invoke<string[]>('convert_excel_files', {
+      const convertedResults = await invoke<ConvertedFile[]>('convert_excel_files', {
         sourcePath: sourcePath.value,
         targetPath: targetPath.value,
-        mappings
+        mappings,
+        schema,
+        importMode
       });
       
-      loggerStore.info(`文件转换完成，共 ${convertedFilePaths.length} 个文件`);
+      loggerStore.info(`文件转换完成，共 ${convertedResults.length} 个文件`);
+      for (const converted of convertedResults) {
+        if (converted.skipped) {
+          loggerStore.warn(`跳过文件 ${converted.source}: ${converted.skipped}`);
+        }
+        for (const skipped of converted.skipped_sheets) {
+          loggerStore.warn(`跳过工作表 ${converted.source} [${skipped.sheet}]: ${skipped.reason}`);
+        }
+        for (const rowError of converted.row_errors) {
+          loggerStore.error(`行处理失败 ${converted.source} [${rowError.sheet}] 第 ${rowError.row} 行: ${rowError.message}`);
+        }
+        if (converted.unmapped_headers.length > 0) {
+          loggerStore.info(`未映射的列 ${converted.source}: ${converted.unmapped_headers.join(', ')}`);
+        }
+        if (converted.unmatched_mappings.length > 0) {
+          const unmatched = converted.unmatched_mappings.map((m) => `${m.original} -> ${m.mapped}`).join(', ');
+          loggerStore.warn(`列映射未匹配 ${converted.source}: ${unmatched}`);
+        }
+        for (const sheet of converted.sheets) {
+          if (sheet.missing_columns.length > 0) {
+            loggerStore.warn(`缺少必需列 ${converted.source} [${sheet.name}]: ${sheet.missing_columns.join(', ')}`);
+          }
+        }
+      }
+      // 拆分输出的每个分卷都需要上传，合并文件由多个源文件共用，只上传一次
+      const convertedFilePaths = [...new Set(convertedResults.flatMap((converted) => converted.outputs))];
       
       // 将文件路径转换为File对象
       const files = await Promise.all(
@@ -200,11 +340,19 @@ export function useFileOperations() {
     convertedFiles: readonly(convertedFiles),
     scanning: readonly(scanning),
     converting: readonly(converting),
+    inspecting: readonly(inspecting),
     canConvert,
     canUpload,
     selectSourceFolder,
     selectTargetFolder,
     scanFiles,
+    inspectFiles,
+    previewWorkbook,
+    previewMapping,
+    suggestMappings,
+    selectSchemaFile,
+    loadSchema,
+    checkMappings,
     startConversion,
     clearConvertedFiles
   };
@@ -3,7 +3,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { httpClient } from '@/utils/httpClient';
 import { useAppStore } from '@/stores';
 import { useLoggerStore } from '@/stores/logger';
-import type { UploadProgress, UploadResponse } from '@/types';
+import type { UploadProgress, UploadResponse, ValidationSchema } from '@/types';
 
 export function useFileUpload() {
   const loggerStore = useLoggerStore();
@@ -15,6 +15,9 @@ export function useFileUpload() {
   const currentUploadIndex = ref(-1);
   const isPaused = ref(false);
   const importMode = ref<'all' | 'inventionOnly' | 'validInventionOnly'>('all');
+  const forceUpload = ref(false);
+  // 设置了目标列定义时，上传前按其中的校验规则检查文件
+  let schema: ValidationSchema | null = null;
 
   const overallProgress = computed(() => {
     if (uploadProgress.value.length === 0) return 0;
@@ -69,6 +72,19 @@ export function useFileUpload() {
       loggerStore.error(errorMsg);
       throw new Error(errorMsg);
     }
+
+    const { schemaPath } = store.settings;
+    schema = null;
+    if (schemaPath) {
+      try {
+        schema = await invoke<ValidationSchema>('load_schema', { path: schemaPath });
+        loggerStore.info(`上传前按目标列定义校验文件: ${schemaPath}`);
+      } catch (error) {
+        const errorMsg = `读取目标列定义失败: ${error instanceof Error ? error.message : String(error)}`;
+        loggerStore.error(errorMsg);
+        throw new Error(errorMsg);
+      }
+    }
     
     uploading.value = true;
     isPaused.value = false;
@@ -149,6 +165,8 @@ export function useFileUpload() {
         serverUrl,
         token,
         importMode: importMode.value,
+        schema,
+        forceUpload: forceUpload.value,
       });
 
       progressItem.status = 'completed';
@@ -158,7 +176,7 @@ export function useFileUpload() {
       
     } catch (error) {
       progressItem.status = 'failed';
-      const errorMsg = error instanceof Error ? error.message : '上传失败';
+      const errorMsg = error instanceof Error ? error.message : typeof error === 'string' ? error : '上传失败';
       progressItem.error = errorMsg;
       loggerStore.error(`文件上传失败 ${file.name}: ${errorMsg}`);
       throw error;
@@ -189,6 +207,10 @@ export function useFileUpload() {
     importMode.value = mode;
   }
 
+  function setForceUpload(force: boolean) {
+    forceUpload.value = force;
+  }
+
   return {
     uploading: readonly(uploading),
     uploadProgress: readonly(uploadProgress),
@@ -203,5 +225,6 @@ export function useFileUpload() {
     retryUpload,
     clearUploadHistory,
     setImportMode,
+    setForceUpload,
   };
 }
\ No newline at end of file
@@ -18,7 +18,11 @@ export const useAppStore = defineStore('app', () => {
     theme: 'light' as 'light' | 'dark',
     autoSave: true,
     logLevel: 'info' as 'debug' | 'info' | 'warn' | 'error',
-    importMode: 'all' as 'all' | 'inventionOnly' | 'validInventionOnly'
+    importMode: 'all' as 'all' | 'inventionOnly' | 'validInventionOnly',
+    // 目标列定义文件路径，为空时不使用
+    schemaPath: '',
+    // 校验未通过的文件仍然上传
+    forceUpload: false
   });
 
   // 保存服务器配置
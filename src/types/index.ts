@@ -3,6 +3,52 @@ export interface ScanResult {
   file_count: number;
   total_size: number;
   files: string[];
+  entries: FileEntry[];
+  duplicate_groups: DuplicateFileGroup[];
+  rejected_files: RejectedFile[];
+}
+
+export interface RejectedFile {
+  path: string;
+  reason: string;
+}
+
+export interface FileEntry {
+  path: string;
+  size: number;
+  modified: string | null;
+  format: 'xlsx' | 'xls' | 'csv' | 'tsv' | 'unknown';
+  sheets: SheetInfo[] | null;
+  error: string | null;
+}
+
+export interface SheetInfo {
+  name: string;
+  header_row: number | null;
+  headers: string[];
+  estimated_rows: number;
+  headers_match: boolean | null;
+  error: string | null;
+}
+
+export interface ScanOptions {
+  hash?: boolean;
+  skip_duplicates?: boolean;
+  inspect?: boolean;
+  mappings?: ColumnMapping[];
+  include?: string[];
+  exclude?: string[];
+  max_depth?: number | null;
+  include_hidden?: boolean;
+  follow_symlinks?: boolean;
+  archives?: boolean;
+  nested_archives?: boolean;
+}
+
+export interface DuplicateFileGroup {
+  hash: string;
+  size: number;
+  files: string[];
 }
 
 export interface ColumnMapping {
@@ -10,6 +56,150 @@ export interface ColumnMapping {
   mapped: string;
 }
 
+export interface SkippedSheet {
+  sheet: string;
+  reason: string;
+}
+
+export interface RowError {
+  sheet: string;
+  row: number;
+  message: string;
+}
+
+export interface ConvertedFile {
+  source: string;
+  target: string;
+  outputs: string[];
+  sheets: ConvertedSheet[];
+  skipped_sheets: SkippedSheet[];
+  rows_written: number;
+  filtered_rows: number;
+  script_skipped_rows: number;
+  row_errors: RowError[];
+  duplicate_rows: DuplicateRow[];
+  merged_rows: MergedRange[];
+  unmapped_headers: string[];
+  unmatched_mappings: ColumnMapping[];
+  missing_columns: string[];
+  skipped: string | null;
+}
+
+export interface ConvertedSheet {
+  name: string;
+  headers: string[];
+  rows: number;
+  mapped_headers: ColumnMapping[];
+  unmapped_headers: string[];
+  missing_columns: string[];
+}
+
+export type RowStatus = 'kept' | 'script_skipped' | 'filtered' | 'failed';
+
+export type CellType = 'empty' | 'string' | 'int' | 'float' | 'bool' | 'date_time' | 'duration' | 'error';
+
+export interface PreviewCell {
+  value: string;
+  type: CellType;
+}
+
+export interface PreviewRow {
+  row: number;
+  cells: PreviewCell[];
+}
+
+export interface WorkbookPreview {
+  path: string;
+  sheets: string[];
+  sheet: string;
+  header_row: number | null;
+  headers: string[];
+  rows: PreviewRow[];
+  total_rows: number;
+}
+
+export interface MappedRow {
+  row: number;
+  values: string[];
+  status: RowStatus;
+  error: string | null;
+}
+
+export interface MappingPreview {
+  path: string;
+  sheet: string;
+  headers: string[];
+  mapped_headers: ColumnMapping[];
+  unmapped_headers: string[];
+  rows: MappedRow[];
+  skipped: string | null;
+}
+
+// 列映射建议
+export interface HeaderFrequency {
+  header: string;
+  sheets: number;
+  files: number;
+}
+
+export type MatchKind = 'exact' | 'alias' | 'normalized' | 'pinyin' | 'fuzzy';
+
+export interface MappingSuggestion {
+  original: string;
+  mapped: string;
+  score: number;
+  match: MatchKind;
+  frequency: number;
+}
+
+export interface MappingSuggestions {
+  headers: HeaderFrequency[];
+  suggestions: MappingSuggestion[];
+  unmatched: string[];
+  failed_files: RejectedFile[];
+  failed_sheets: FailedSheet[];
+}
+
+export interface FailedSheet {
+  file: string;
+  sheet: string;
+  reason: string;
+}
+
+export interface MergedRange {
+  sheet: string;
+  source_row: number;
+  output: string;
+  output_row: number;
+  rows: number;
+}
+
+export interface RowLocation {
+  file: string;
+  sheet: string;
+  row: number;
+}
+
+export interface DuplicateRow {
+  sheet: string;
+  row: number;
+  key: string;
+  removed: boolean;
+  kept: RowLocation;
+}
+
+export interface DuplicateGroup {
+  key: string;
+  locations: RowLocation[];
+  kept: number;
+}
+
+export interface DuplicateReport {
+  groups: DuplicateGroup[];
+  duplicate_rows: number;
+  failed_sheets: FailedSheet[];
+}
+
 export interface ServerConfig {
   serverUrl: string;
   username: string;
@@ -62,4 +252,61 @@ export interface LogEntry {
 export interface ConnectionStatus {
   type: 'success' | 'warning' | 'error' | 'info';
   message: string;
-}
\ No newline at end of file
+}
+// 校验接口
+export interface ValidationIssue {
+  file: string;
+  sheet: string;
+  row: number;
+  column: string;
+  value: string;
+  message: string;
+}
+
+export interface FileValidation {
+  file: string;
+  passed: boolean;
+  rows_checked: number;
+  issues: ValidationIssue[];
+}
+
+export interface ValidationReport {
+  import_mode: 'all' | 'inventionOnly' | 'validInventionOnly';
+  files: FileValidation[];
+}
+
+// 目标列定义
+export type ImportMode = 'all' | 'inventionOnly' | 'validInventionOnly';
+
+export interface ColumnRule {
+  name: string;
+  required?: boolean;
+  not_empty?: boolean;
+  type?: 'text' | 'integer' | 'number' | 'date' | 'application_number';
+  date_format?: string | null;
+  allowed_values?: string[];
+  aliases?: string[];
+}
+
+export interface ColumnRename {
+  from: string;
+  to: string;
+}
+
+export interface SchemaMigration {
+  version: number;
+  renames: ColumnRename[];
+}
+
+export interface ValidationSchema {
+  version: number;
+  columns: ColumnRule[];
+  modes: Partial<Record<ImportMode, ColumnRule[]>>;
+  migrations: SchemaMigration[];
+}
+
+export interface SchemaCheck {
+  migrated: ColumnRename[];
+  alias_mappings: ColumnMapping[];
+  issues: string[];
+}